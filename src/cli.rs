@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 
 #[derive(Parser)]
@@ -24,46 +24,224 @@ pub enum Subcommands {
     Begin {
         /// Optional duration between the start of the day and the command was entered, positive delay implies an earlier start.
         #[clap(short, long, value_parser, value_name = "DURATION")]
-        duration: Option<String>
+        duration: Option<String>,
+        /// Optional absolute time of the event, as "YYYY-MM-DD HH:MM" or "HH:MM" for today. The event is inserted at its chronological position.
+        #[clap(short, long, value_parser, value_name = "TIME", conflicts_with = "duration")]
+        at: Option<String>
     },
 
     /// Command for creating an end work event.
     End {
         /// Optional duration between the end of the day and the command was entered, positive delay implies a later end.
         #[clap(short, long, value_parser, value_name = "DURATION")]
-        duration: Option<String>
+        duration: Option<String>,
+        /// Optional absolute time of the event, as "YYYY-MM-DD HH:MM" or "HH:MM" for today. The event is inserted at its chronological position.
+        #[clap(short, long, value_parser, value_name = "TIME", conflicts_with = "duration")]
+        at: Option<String>
     },
 
     /// Command for entering a pause event, such as a lunch break.
     Pause {
         /// Duration of break as a parable string, for example "30m".
-        #[clap(value_parser)]
-        duration: String
+        #[clap(value_parser, required_unless_present = "from")]
+        duration: Option<String>,
+        /// Optional absolute start time of the break, as "YYYY-MM-DD HH:MM" or "HH:MM" for today. The event is inserted at its chronological position.
+        #[clap(short, long, value_parser, value_name = "TIME")]
+        at: Option<String>,
+        /// Start time of a break given by its start and end instead of a duration.
+        #[clap(short, long, value_parser, value_name = "TIME", requires = "to", conflicts_with_all = ["duration", "at"])]
+        from: Option<String>,
+        /// End time of a break given by its start and end.
+        #[clap(short, long, value_parser, value_name = "TIME", requires = "from")]
+        to: Option<String>
+    },
+
+    /// Command for starting a break of unknown length, ended by "tt resume".
+    Break {
+        /// Optional absolute time of the event, as "YYYY-MM-DD HH:MM" or "HH:MM" for today. The event is inserted at its chronological position.
+        #[clap(short, long, value_parser, value_name = "TIME")]
+        at: Option<String>
+    },
+
+    /// Command for ending a break started by "tt break".
+    Resume {
+        /// Optional absolute time of the event, as "YYYY-MM-DD HH:MM" or "HH:MM" for today. The event is inserted at its chronological position.
+        #[clap(short, long, value_parser, value_name = "TIME")]
+        at: Option<String>
     },
 
     /// Command for switching between projects.
     Switch {
         /// Unique name of project to switch to.
         #[clap(value_parser)]
-        project: String
+        project: String,
+        /// Optional absolute time of the event, as "YYYY-MM-DD HH:MM" or "HH:MM" for today. The event is inserted at its chronological position.
+        #[clap(short, long, value_parser, value_name = "TIME")]
+        at: Option<String>
     },
 
     /// Command to erase the last event, useful in case of mistyping.
     Nevermind,
 
+    /// Command for changing the time, duration or project of an event, identified by its index in "tt log".
+    Edit {
+        /// Index of the event to change.
+        #[clap(value_parser)]
+        index: usize,
+        /// New time of the event, as "YYYY-MM-DD HH:MM" or "HH:MM" for today.
+        #[clap(short, long, value_parser, value_name = "TIME")]
+        time: Option<String>,
+        /// New duration of a pause event, for example "30m".
+        #[clap(short, long, value_parser, value_name = "DURATION")]
+        duration: Option<String>,
+        /// New project of a switch event.
+        #[clap(short, long, value_parser, value_name = "PROJECT")]
+        project: Option<String>
+    },
+
+    /// Command for removing an event, identified by its index in "tt log".
+    Delete {
+        /// Index of the event to remove.
+        #[clap(value_parser)]
+        index: usize
+    },
+
+    /// Command for writing the end of days that were never ended, as inferred by the auto end policy.
+    Fix,
+
     /// Command to open the new project wizard.
     New,
 
     /// Command to list registered projects.
     Projects,
 
+    /// Command to list the events in the timesheet with their indices.
+    Log,
+
+    /// Command for exporting the recorded work to other tools.
+    Export {
+        /// Export the work sessions as iCalendar events.
+        #[clap(long, action, required = true)]
+        ics: bool,
+        /// Write the export to this file instead of stdout, such as "work.ics".
+        #[clap(short, long, value_parser, value_name = "FILE")]
+        output: Option<String>
+    },
+
     /// The main tool for generating nice timetracking reports.
-    Show,
+    Show {
+        /// Only show work from this date on, as "YYYY-MM-DD".
+        #[clap(long, value_parser, value_name = "DATE", conflicts_with = "period")]
+        from: Option<String>,
+        /// Only show work until and including this date, as "YYYY-MM-DD".
+        #[clap(long, value_parser, value_name = "DATE", conflicts_with = "period")]
+        to: Option<String>,
+        /// Only show one ISO week of this year, the current week when no number is given.
+        #[clap(long, value_parser, value_name = "WEEK", num_args = 0..=1, group = "period")]
+        week: Option<Option<u32>>,
+        /// Only show one month, as "YYYY-MM", the current month when none is given.
+        #[clap(long, value_parser, value_name = "MONTH", num_args = 0..=1, group = "period")]
+        month: Option<Option<String>>,
+        /// Only show the current week and the weeks before it, this many weeks in total.
+        #[clap(long, value_parser = clap::value_parser!(u32).range(1..), value_name = "WEEKS", group = "period")]
+        last: Option<u32>,
+        /// Only show today.
+        #[clap(long, action, group = "period")]
+        today: bool,
+        /// Show the timeline of the events on a date, as "YYYY-MM-DD", today when no date is given.
+        #[clap(long, value_parser, value_name = "DATE", num_args = 0..=1, group = "period", conflicts_with = "view")]
+        day: Option<Option<String>>,
+        /// Output format, overriding default_output in the config.
+        #[clap(long, value_enum, value_name = "FORMAT")]
+        format: Option<OutputFormat>,
+        /// Write the report to this file instead of stdout.
+        #[clap(short, long, value_parser, value_name = "FILE")]
+        output: Option<String>,
+        /// Periods to sum the work over, months and years also give worked days and the average per day.
+        #[clap(long, value_enum, value_name = "VIEW", default_value = "week")]
+        view: ReportView,
+        /// Subtotal the projects of each week by category or project ID, only in the week view.
+        #[clap(long, value_enum, value_name = "FIELD", conflicts_with = "day")]
+        group_by: Option<GroupField>
+    },
+
+    /// Command for the flex balance, the work over the whole timesheet against the schedule in the config.
+    Balance {
+        /// Show every day, not only the weeks.
+        #[clap(long, action)]
+        days: bool,
+        /// Output format, overriding default_output in the config.
+        #[clap(long, value_enum, value_name = "FORMAT")]
+        format: Option<OutputFormat>
+    },
 
-    /// Command for switching between different timesheets.
+    /// Command for switching between different timesheets, or managing the current one.
+    #[clap(args_conflicts_with_subcommands = true)]
     Sheet {
-        /// Absolute path to timesheet file, regular or empty text file.
+        /// Absolute path to timesheet file, regular or empty text file. Prints the current timesheet when left out.
         #[clap(value_name = "SHEET_FILE", value_parser)]
-        sheet_name: String
+        sheet_name: Option<String>,
+
+        #[clap(subcommand)]
+        action: Option<SheetCommands>
+    }
+}
+
+
+#[derive(Subcommand)]
+pub enum SheetCommands {
+
+    /// Command for rewriting the current timesheet in another storage format.
+    Convert {
+        /// Storage format to convert to.
+        #[clap(value_enum)]
+        format: StorageFormat
     }
+}
+
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// Tables of the work per week.
+    Default,
+    /// One JSON document.
+    Json,
+    /// One row per day per project.
+    Csv,
+    /// Markdown tables.
+    Markdown,
+    /// A self-contained HTML page.
+    Html
+}
+
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GroupField {
+    /// The category of the projects.
+    Category,
+    /// The project ID of the projects, shared by projects booked on the same ID.
+    #[clap(name = "project_id")]
+    ProjectId
+}
+
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportView {
+    /// A table per week with the work per day.
+    Week,
+    /// A summary per month, per project and category.
+    Month,
+    /// A summary per year, per project and category.
+    Year
+}
+
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StorageFormat {
+    /// One JSON document holding every event.
+    Json,
+    /// One event per line, new events are appended.
+    Jsonl,
+    /// An SQLite database, written next to the current sheet.
+    Sqlite
 }
\ No newline at end of file
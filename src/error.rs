@@ -0,0 +1,109 @@
+use chrono::prelude::*;
+
+use std::fmt;
+use std::io;
+
+use crate::events::TransitionError;
+
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Every way a timetrack library call can fail.
+#[derive(Debug)]
+pub enum Error {
+    /// The file at path exists, but its content is not a timesheet.
+    UnreadableSheet { path: String, source: serde_json::Error },
+    /// The config file at path exists, but its content is not a config.
+    UnreadableConfig { path: String, source: serde_json::Error },
+    /// Turning what into JSON failed.
+    Serialization { what: String, source: serde_json::Error },
+    /// The timesheet file at path has a format version this version of timetrack does not know.
+    UnsupportedSheetVersion { path: String, version: u32 },
+    /// The string could not be parsed as a duration.
+    BadDuration(String),
+    /// No registered project matches the identifier.
+    UnknownProject(String),
+    /// The string could not be parsed as a point in time.
+    BadTime(String),
+    /// The string could not be parsed as a date, month or week.
+    BadDate(String),
+    /// The interval ends before it starts.
+    EmptyInterval { from: DateTime<Local>, to: DateTime<Local> },
+    /// The event is not allowed in the current state of the timesheet.
+    IllegalTransition(TransitionError),
+    /// A change to the timesheet would make the event at index illegal.
+    IllegalSequence { index: usize, error: TransitionError },
+    /// The event at index would no longer be in chronological order.
+    OutOfOrder(usize),
+    /// There is no event at the index in the timesheet.
+    NoSuchEvent(usize),
+    /// The event does not carry the field that was asked to be edited.
+    InapplicableEdit { event: String, field: &'static str },
+    /// The flex balance was asked for without a schedule in the config.
+    NoSchedule,
+    /// The report view has no renderer in the output format.
+    UnsupportedView { view: String, format: String },
+    /// Reading or writing the file at path failed.
+    Io { path: String, source: io::Error },
+    /// A query on the SQLite timesheet at path failed.
+    Database { path: String, source: rusqlite::Error }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnreadableSheet { path, source } => write!(f, "Timesheet file at {} was unreadable as a timesheet: {}", path, source),
+            Self::UnreadableConfig { path, source } => write!(f, "Config file at {} was unreadable as a config: {}", path, source),
+            Self::Serialization { what, source } => write!(f, "Unable to write {} as JSON: {}", what, source),
+            Self::UnsupportedSheetVersion { path, version } => write!(f, "Timesheet file at {} has unknown format version {}, it may be written by a newer version of timetrack.", path, version),
+            Self::BadDuration(duration) => write!(f, "Unable to parse {} into a duration.", duration),
+            Self::UnknownProject(project) => write!(f, "Could not find project identified by {}. Try creating job first with \"tt new\" or list projects with \"tt projects\".", project),
+            Self::BadTime(time) => write!(f, "Unable to parse {} into a time, use \"YYYY-MM-DD HH:MM\" or \"HH:MM\".", time),
+            Self::BadDate(date) => write!(f, "Unable to parse {} into a date, use \"YYYY-MM-DD\", or \"YYYY-MM\" for a month.", date),
+            Self::EmptyInterval { from, to } => write!(f, "The interval from {} to {} is empty, the end must be after the start.", from.format("%Y-%m-%d %H:%M"), to.format("%Y-%m-%d %H:%M")),
+            Self::IllegalTransition(error) => write!(f, "{}, event not written.", error),
+            Self::IllegalSequence { index, error } => write!(f, "{} at event {}, timesheet not changed.", error, index),
+            Self::OutOfOrder(index) => write!(f, "Event {} would be out of chronological order, timesheet not changed.", index),
+            Self::NoSuchEvent(index) => write!(f, "There is no event {} in the timesheet, list events with \"tt log\".", index),
+            Self::InapplicableEdit { event, field } => write!(f, "Event {} has no {} to edit.", event, field),
+            Self::NoSchedule => write!(f, "There is no schedule of expected hours in the config, add one to count a flex balance."),
+            Self::UnsupportedView { view, format } => write!(f, "The {} view can not be shown as {}, use the default or json format.", view, format),
+            Self::Io { path, source } => write!(f, "Unable to access {}: {}", path, source),
+            Self::Database { path, source } => write!(f, "Timesheet database at {} failed: {}", path, source)
+        }
+    }
+}
+
+impl Error {
+    /// For mapping an io::Error on the file at path.
+    pub(crate) fn io(path: &str) -> impl FnOnce(io::Error) -> Error + '_ {
+        move |source| Error::Io { path: path.to_owned(), source }
+    }
+
+    /// For mapping a serde_json::Error on writing what.
+    pub(crate) fn serialization(what: &str) -> impl FnOnce(serde_json::Error) -> Error + '_ {
+        move |source| Error::Serialization { what: what.to_owned(), source }
+    }
+
+    /// For mapping an rusqlite::Error on the database at path.
+    pub(crate) fn database(path: &str) -> impl FnOnce(rusqlite::Error) -> Error + '_ {
+        move |source| Error::Database { path: path.to_owned(), source }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnreadableSheet { source, .. } | Self::UnreadableConfig { source, .. } | Self::Serialization { source, .. } => Some(source),
+            Self::IllegalTransition(error) | Self::IllegalSequence { error, .. } => Some(error),
+            Self::Io { source, .. } => Some(source),
+            Self::Database { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+impl From<TransitionError> for Error {
+    fn from(error: TransitionError) -> Self { Error::IllegalTransition(error) }
+}
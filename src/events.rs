@@ -1,139 +1,556 @@
 use serde::{Serialize, Deserialize};
 use chrono::prelude::*;
-use parse_duration;
+use prettytable::{Table, row, format};
 
-use std::fs;
 use std::time::Duration;
-use std::process::exit;
 use std::fmt;
 
-use crate::options::{JobType, Options};
+use crate::error::{Error, Result};
+use crate::options::{AutoEnd, JobType, Options};
+use crate::store::{open_store, lock_sheet, read_sheet};
 
 
 pub type Sheet = Vec<Event>;
 
-fn check_begun(sheet: &Sheet, time: Option<DateTime<Local>>) -> Option<bool> {
-    let now = time.unwrap_or(Local::now());
-    for event in sheet.iter().rev() {
-        match event {
-            Event::BEGIN(time) => {
-                if time < &now { return Some(true); } else { continue; };
-            },
-            Event::END(time) => {
-                if time < &now { return Some(false); } else { continue; };
-            },
-            _ => continue
+/// The state a timesheet is in after replaying its events in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SheetState {
+    #[default]
+    Ended,
+    Begun,
+    OnBreak
+}
+
+impl SheetState {
+    /// Returns the state after applying event, or the reason the event is illegal in the current state.
+    pub fn transition(self, event: &Event) -> std::result::Result<SheetState, TransitionError> {
+        use SheetState::*;
+
+        match (self, event) {
+            (Ended, Event::BEGIN(_)) => Ok(Begun),
+            (Begun, Event::BEGIN(_)) => Err(TransitionError::BeginWhileBegun),
+            (OnBreak, Event::BEGIN(_)) => Err(TransitionError::BeginWhileOnBreak),
+            (Begun | OnBreak, Event::END(_)) => Ok(Ended),
+            (Ended, Event::END(_)) => Err(TransitionError::EndWhileEnded),
+            (Begun, Event::PAUSE(_) | Event::TIMEDPAUSE(..)) => Ok(Begun),
+            (Ended, Event::PAUSE(_) | Event::TIMEDPAUSE(..)) => Err(TransitionError::PauseWhileEnded),
+            (OnBreak, Event::PAUSE(_) | Event::TIMEDPAUSE(..)) => Err(TransitionError::PauseWhileOnBreak),
+            (Begun, Event::BREAK(_)) => Ok(OnBreak),
+            (Ended, Event::BREAK(_)) => Err(TransitionError::BreakWhileEnded),
+            (OnBreak, Event::BREAK(_)) => Err(TransitionError::BreakWhileOnBreak),
+            (OnBreak, Event::RESUME(_)) => Ok(Begun),
+            (Begun | Ended, Event::RESUME(_)) => Err(TransitionError::ResumeWithoutBreak),
+            (state, Event::SWITCH(..)) => Ok(state)
+        }
+    }
+}
+
+
+/// Reasons an event can not follow the events before it in a timesheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionError {
+    BeginWhileBegun,
+    BeginWhileOnBreak,
+    EndWhileEnded,
+    PauseWhileEnded,
+    PauseWhileOnBreak,
+    BreakWhileEnded,
+    BreakWhileOnBreak,
+    ResumeWithoutBreak
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BeginWhileBegun => write!(f, "Illegal event BEGIN while sheet in begun state"),
+            Self::BeginWhileOnBreak => write!(f, "Illegal event BEGIN while sheet in break state, use \"tt resume\" to end the break"),
+            Self::EndWhileEnded => write!(f, "Illegal event END while sheet in ended state"),
+            Self::PauseWhileEnded => write!(f, "Illegal event PAUSE while sheet in ended state"),
+            Self::PauseWhileOnBreak => write!(f, "Illegal event PAUSE while sheet in break state"),
+            Self::BreakWhileEnded => write!(f, "Illegal event BREAK while sheet in ended state"),
+            Self::BreakWhileOnBreak => write!(f, "Illegal event BREAK while sheet in break state"),
+            Self::ResumeWithoutBreak => write!(f, "Illegal event RESUME while sheet not in break state")
+        }
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+
+/// Replays the whole sheet through the state machine, returning the final state and every illegal event by index.
+/// Illegal events are skipped, so the state is the one the remaining legal events lead to.
+pub fn replay_sheet(sheet: &[Event]) -> (SheetState, Vec<(usize, TransitionError)>) {
+    let mut state = SheetState::default();
+    let mut illegal = vec![];
+
+    for (index, event) in sheet.iter().enumerate() {
+        match state.transition(event) {
+            Ok(next) => state = next,
+            Err(error) => illegal.push((index, error))
         }
     }
-    None
+
+    (state, illegal)
+}
+
+/// Checks whether event may follow the events in sheet.
+/// With an auto end policy a BEGIN on a later date than an open day is allowed, the policy or "tt fix" ends such days.
+pub fn check_event(sheet: &[Event], event: &Event, auto_end: AutoEnd) -> Result<SheetState> {
+    let (state, _) = replay_sheet(sheet);
+    match state.transition(event) {
+        Err(error) if ends_day_automatically(sheet, event, error, auto_end) => Ok(SheetState::Begun),
+        result => Ok(result?)
+    }
 }
 
 
-pub fn begin(config: &Options, duration: &Option<String>, verbose: bool) {
-    let offset = if let Some(str_duration) = duration {
-        chrono::Duration::from_std(parse_duration::parse(str_duration).expect(&format!("Unable to parse {} into a duration.", str_duration))).unwrap()
-    } else {
-        chrono::Duration::from_std(parse_duration::parse("0m").unwrap()).unwrap()
+/// A change to one event of a sheet.
+#[derive(Debug, Clone, Copy)]
+enum Change {
+    Insert(usize),
+    /// Replacing an event keeps every index.
+    Replace,
+    Remove(usize)
+}
+
+impl Change {
+    /// Index before the change of the event at index after it, None for an inserted event.
+    fn index_before(self, index: usize) -> Option<usize> {
+        match self {
+            Self::Insert(at) if index == at => None,
+            Self::Insert(at) if index > at => Some(index - 1),
+            Self::Remove(at) if index >= at => Some(index + 1),
+            _ => Some(index)
+        }
+    }
+}
+
+/// Checks that every illegal event in the sheet after change was already illegal for the same reason before it.
+/// With an auto end policy a BEGIN on a later date than an open day is tolerated, the policy or "tt fix" ends such days.
+fn check_changed_sheet(before: &Sheet, after: &Sheet, change: Change, auto_end: AutoEnd) -> Result<()> {
+    let illegal = |sheet: &Sheet| -> Vec<(usize, TransitionError)> {
+        let (_, illegal) = replay_sheet(sheet);
+        illegal.into_iter().filter(|&(index, error)| !ends_day_automatically(&sheet[..index], &sheet[index], error, auto_end)).collect()
     };
-    
-    let begin_event = Event::BEGIN(Local::now() - offset);
-    let mut sheet = read_sheet(&config.timesheet);
-    
-    if let Some(true) = check_begun(&sheet, Some(Local::now() - offset)) {
-        eprintln!("Illegal event BEGIN while sheet in begun state, event not written.");
-        exit(1);
+    let illegal_before = illegal(before);
+
+    for (index, error) in illegal(after) {
+        let was_illegal = change.index_before(index).is_some_and(|index_before| illegal_before.contains(&(index_before, error)));
+        if !was_illegal {
+            return Err(Error::IllegalSequence { index, error });
+        }
     }
+    Ok(())
+}
 
-    sheet.push(begin_event);
-    write_sheet(sheet, &config.timesheet);
+/// Whether event is a BEGIN illegal only because the day before it is still open, which auto_end will end.
+fn ends_day_automatically(before: &[Event], event: &Event, error: TransitionError, auto_end: AutoEnd) -> bool {
+    matches!(error, TransitionError::BeginWhileBegun | TransitionError::BeginWhileOnBreak)
+        && !matches!(auto_end, AutoEnd::Never)
+        && begins_new_day(before, event)
+}
 
-    if verbose { println!("Wrote begin to timesheet at {}", &config.timesheet); }
+/// Whether event is a BEGIN on a later date than the last BEGIN in before.
+fn begins_new_day(before: &[Event], event: &Event) -> bool {
+    let begun = match event {
+        Event::BEGIN(time) => time.naive_local().date(),
+        _ => return false
+    };
+    before.iter().rev().find_map(|event| match event {
+        Event::BEGIN(time) => Some(time.naive_local().date() < begun),
+        _ => None
+    }).unwrap_or(false)
 }
 
-pub fn end(config: &Options, duration: &Option<String>, verbose: bool) {
-    let offset = if let Some(str_duration) = duration {
-        chrono::Duration::from_std(parse_duration::parse(str_duration).expect(&format!("Unable to parse {} into a duration.", str_duration))).unwrap()
-    } else {
-        chrono::Duration::from_std(parse_duration::parse("0m").unwrap()).unwrap()
+/// Checks that the event at index is not earlier than the timed event before it, nor later than the one after it.
+fn check_order(sheet: &Sheet, index: usize) -> Result<()> {
+    let time = match sheet.get(index).and_then(Event::time) {
+        Some(time) => time,
+        None => return Ok(())
     };
+    let previous = sheet[..index].iter().rev().find_map(Event::time);
+    let next = sheet[index + 1..].iter().find_map(Event::time);
+
+    if previous.is_some_and(|previous| previous > time) || next.is_some_and(|next| next < time) {
+        return Err(Error::OutOfOrder(index));
+    }
+    Ok(())
+}
+
+
+/// A day that was begun, but never ended before the BEGIN of a later day.
+#[derive(Debug, Clone, Copy)]
+pub struct OpenDay {
+    /// Time of the BEGIN event opening the day.
+    pub begun: DateTime<Local>,
+    /// Time of the last event recorded before the next BEGIN, on any date.
+    pub last_event: DateTime<Local>,
+    /// Position in the sheet where an END closing the day belongs.
+    pub index: usize
+}
+
+impl OpenDay {
+    /// Whether work on the day may still be going on at now, on the date it was begun or until the policy would end it.
+    pub fn going_on(&self, auto_end: AutoEnd, now: DateTime<Local>) -> bool {
+        self.begun.naive_local().date() == now.naive_local().date()
+            || auto_end.planned_end(self).is_some_and(|end| end > now)
+    }
+}
+
+/// Finds every day in the sheet that was never ended, in sheet order.
+/// The day begun last is only counted once it is no longer going on under auto_end.
+pub fn open_days(sheet: &Sheet, auto_end: AutoEnd) -> Vec<OpenDay> {
+    let mut open_days = vec![];
+    let mut open: Option<OpenDay> = None;
+
+    for (index, event) in sheet.iter().enumerate() {
+        match (event, open.as_mut()) {
+            (Event::BEGIN(time), Some(day)) => {
+                if time.naive_local().date() > day.begun.naive_local().date() {
+                    open_days.push(*day);
+                    open = Some(OpenDay { begun: *time, last_event: *time, index: index + 1 });
+                }
+            },
+            (Event::BEGIN(time), None) => {
+                open = Some(OpenDay { begun: *time, last_event: *time, index: index + 1 });
+            },
+            (Event::END(_), Some(_)) => { open = None; },
+            (event, Some(day)) => {
+                day.last_event = event.time().unwrap_or(day.last_event);
+                day.index = index + 1;
+            },
+            (_, None) => continue
+        }
+    }
+
+    open_days.extend(open.filter(|day| !day.going_on(auto_end, Local::now())));
+    open_days
+}
 
-    let end_event = Event::END(Local::now() + offset);
 
-    let mut sheet = read_sheet(&config.timesheet);
+fn parse_std_duration(duration: &str) -> Result<Duration> {
+    parse_duration::parse(duration).map_err(|_| Error::BadDuration(duration.to_owned()))
+}
+
+/// Parses "YYYY-MM-DD HH:MM", or "HH:MM" for today, as a local time.
+pub fn parse_time(time: &str) -> Result<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M").map(|clock| Local::now().naive_local().date().and_time(clock)))
+        .map_err(|_| Error::BadTime(time.to_owned()))?;
+    Local.from_local_datetime(&naive).earliest().ok_or_else(|| Error::BadTime(time.to_owned()))
+}
 
-    if let Some(false) = check_begun(&sheet, Some(Local::now() + offset)) {
-        eprintln!("Illegal event END while sheet in ended state, event not written.");
-        exit(1);
+fn parse_offset(duration: &Option<String>) -> Result<chrono::Duration> {
+    match duration {
+        Some(str_duration) => chrono::Duration::from_std(parse_std_duration(str_duration)?)
+            .map_err(|_| Error::BadDuration(str_duration.clone())),
+        None => Ok(chrono::Duration::zero())
     }
+}
+
+
+/// Writes event to the end of the sheet, or at the chronological position of at when given.
+fn write_event(config: &Options, event: Event, at: Option<DateTime<Local>>) -> Result<()> {
+    let _lock = lock_sheet(&config.timesheet)?;
+    let mut store = open_store(&config.timesheet)?;
+    let sheet = store.load()?;
+
+    match at {
+        None => {
+            check_event(&sheet, &event, config.auto_end)?;
+            store.append(event)
+        },
+        Some(time) => {
+            let index = chronological_index(&sheet, time);
+            check_event(&sheet[..index], &event, config.auto_end)?;
+
+            let mut changed = sheet.clone();
+            changed.insert(index, event.clone());
+            check_changed_sheet(&sheet, &changed, Change::Insert(index), config.auto_end)?;
+            store.insert(index, event)
+        }
+    }
+}
+
+/// The position after every event at or before time, a PAUSE counts as happening at the event before it.
+fn chronological_index(sheet: &Sheet, time: DateTime<Local>) -> usize {
+    let mut index = 0;
+    let mut last_time = None;
+
+    for (position, event) in sheet.iter().enumerate() {
+        last_time = event.time().or(last_time);
+        if last_time.is_none_or(|last_time| last_time <= time) {
+            index = position + 1;
+        }
+    }
+
+    index
+}
+
+fn parse_at(at: &Option<String>) -> Result<Option<DateTime<Local>>> {
+    at.as_deref().map(parse_time).transpose()
+}
+
+/// The time of an event moved by offset, which then goes in at its chronological position like one given with --at.
+fn moved_at(offset: chrono::Duration, time: DateTime<Local>) -> Option<DateTime<Local>> {
+    (offset != chrono::Duration::zero()).then_some(time)
+}
+
+
+pub fn begin(config: &Options, duration: &Option<String>, at: &Option<String>, verbose: bool) -> Result<()> {
+    let offset = parse_offset(duration)?;
+    let at = parse_at(at)?;
     
-    sheet.push(end_event);
-    write_sheet(sheet, &config.timesheet);
+    let time = at.unwrap_or(Local::now() - offset);
+    write_event(config, Event::BEGIN(time), at.or(moved_at(offset, time)))?;
+
+    if verbose { println!("Wrote begin to timesheet at {}", &config.timesheet); }
+    Ok(())
+}
+
+pub fn end(config: &Options, duration: &Option<String>, at: &Option<String>, verbose: bool) -> Result<()> {
+    let offset = parse_offset(duration)?;
+    let at = parse_at(at)?;
+
+    let time = at.unwrap_or(Local::now() + offset);
+    write_event(config, Event::END(time), at.or(moved_at(offset, time)))?;
 
     if verbose { println!("Wrote end to timesheet at {}", &config.timesheet); }
+    Ok(())
+}
+
+/// Writes a pause of a duration, starting at at when given.
+pub fn pause(config: &Options, pause_time: &str, at: &Option<String>, verbose: bool) -> Result<()> {
+    let pause_duration = parse_std_duration(pause_time)?;
+    let at = parse_at(at)?;
+
+    let pause_event = match at {
+        Some(from) => Event::TIMEDPAUSE(from, from + chrono::Duration::from_std(pause_duration).map_err(|_| Error::BadDuration(pause_time.to_owned()))?),
+        None => Event::PAUSE(pause_duration)
+    };
+    write_event(config, pause_event, at)?;
+
+    if verbose { println!("Wrote pause {} to timesheet at {}", &pause_time, &config.timesheet); }
+    Ok(())
 }
 
-pub fn pause(config: &Options, pause_time: &str, verbose: bool) {
-    let pause_duration = parse_duration::parse(pause_time)
-        .expect(&format!("Unable to parse {} into a duration.", pause_time));
+/// Writes a pause between two times.
+pub fn pause_between(config: &Options, from: &str, to: &str, verbose: bool) -> Result<()> {
+    let (from, to) = (parse_time(from)?, parse_time(to)?);
+    if to <= from { return Err(Error::EmptyInterval { from, to }); }
 
-    let pause_event = Event::PAUSE(pause_duration);
+    write_event(config, Event::TIMEDPAUSE(from, to), Some(from))?;
 
-    let mut sheet = read_sheet(&config.timesheet);
+    if verbose { println!("Wrote pause from {} to {} to timesheet at {}", from.format("%H:%M"), to.format("%H:%M"), &config.timesheet); }
+    Ok(())
+}
+
+pub fn switch(config: &Options, into: String, at: &Option<String>, verbose: bool) -> Result<()> {
+    let job_id = JobIdentifier::parse(&into);
+    let at = parse_at(at)?;
 
-    if let Some(false) = check_begun(&sheet, None) {
-        eprintln!("Illegal event PAUSE while sheet in ended state, event not written.");
-        exit(1);
+    if job_id.get_jobtype(config).is_none() {
+        return Err(Error::UnknownProject(into));
     }
 
-    sheet.push(pause_event);
-    write_sheet(sheet, &config.timesheet);
+    let switch_event = Event::SWITCH(at.unwrap_or(Local::now()), job_id);
+    write_event(config, switch_event, at)?;
 
-    if verbose { println!("Wrote pause {} to timesheet at {}", &pause_time, &config.timesheet); }
+    if verbose { println!("Wrote switch to {} to timesheet at {}", &into, &config.timesheet); }
+    Ok(())
 }
 
-pub fn switch(config: &Options, into: String, verbose: bool) {
-    let job_id = match into.clone().parse::<usize>() {
-        Ok(id) => JobIdentifier::InternalId(id),
-        Err(_) => JobIdentifier::UName(into.clone())
-    };
+/// Starts an open-ended break, the break lasts until "tt resume" or the end of the day.
+pub fn take_break(config: &Options, at: &Option<String>, verbose: bool) -> Result<()> {
+    let at = parse_at(at)?;
+
+    write_event(config, Event::BREAK(at.unwrap_or(Local::now())), at)?;
+
+    if verbose { println!("Wrote break to timesheet at {}", &config.timesheet); }
+    Ok(())
+}
+
+pub fn resume(config: &Options, at: &Option<String>, verbose: bool) -> Result<()> {
+    let at = parse_at(at)?;
+
+    write_event(config, Event::RESUME(at.unwrap_or(Local::now())), at)?;
 
-    if let None = job_id.get_jobtype(config) {
-        eprintln!("Could not find project identified by {}. Try creating job first with \"timetrack new\" or list projects with \"timetrack projects\". Event not written.", &into);
-        exit(1);
+    if verbose { println!("Wrote resume to timesheet at {}", &config.timesheet); }
+    Ok(())
+}
+
+pub fn fix(config: &Options, verbose: bool) -> Result<()> {
+    let _lock = lock_sheet(&config.timesheet)?;
+    let mut store = open_store(&config.timesheet)?;
+    let mut sheet = store.load()?;
+    let days = open_days(&sheet, config.auto_end);
+
+    if days.is_empty() {
+        println!("No unterminated days in timesheet {}", &config.timesheet);
+        return Ok(());
     }
 
-    let switch_event = Event::SWITCH(Local::now(), job_id);
+    let mut fixed = 0;
+    for day in days.iter().rev() {
+        if let Some(time) = config.auto_end.end_time(day) {
+            let mut ended = sheet.clone();
+            ended.insert(day.index, Event::END(time));
+            check_order(&ended, day.index)?;
+            check_changed_sheet(&sheet, &ended, Change::Insert(day.index), config.auto_end)?;
+            sheet = ended;
+            fixed += 1;
+        }
+    }
 
-    let mut sheet = read_sheet(&config.timesheet);
-    sheet.push(switch_event);
-    write_sheet(sheet, &config.timesheet);
+    for day in days.iter() {
+        match config.auto_end.end_time(day) {
+            Some(time) => println!("Day begun {} ended at {}", day.begun.format("%Y-%m-%d %H:%M"), time.format("%H:%M")),
+            None => println!("Day begun {} was never ended, set an auto_end policy in the config to end it.", day.begun.format("%Y-%m-%d %H:%M"))
+        }
+    }
 
-    if verbose { println!("Wrote switch to {} to timesheet at {}", &into, &config.timesheet); }
+    if fixed > 0 {
+        store.write_all(sheet)?;
+        if verbose { println!("Wrote {} inferred end events to timesheet at {}", fixed, &config.timesheet); }
+    }
+    Ok(())
+}
+
+pub fn edit(config: &Options, index: usize, time: &Option<String>, duration: &Option<String>, project: &Option<String>, verbose: bool) -> Result<()> {
+    let _lock = lock_sheet(&config.timesheet)?;
+    let mut store = open_store(&config.timesheet)?;
+    let sheet = store.load()?;
+    let mut edited = sheet.clone();
+    let event = edited.get_mut(index).ok_or(Error::NoSuchEvent(index))?;
+
+    if let Some(time) = time {
+        match event {
+            Event::BEGIN(at) | Event::END(at) | Event::SWITCH(at, _) | Event::BREAK(at) | Event::RESUME(at) => *at = parse_time(time)?,
+            Event::TIMEDPAUSE(from, to) => {
+                let moved_from = parse_time(time)?;
+                *to = moved_from + (*to - *from);
+                *from = moved_from;
+            },
+            Event::PAUSE(_) => return Err(Error::InapplicableEdit { event: event.to_string(), field: "time" })
+        }
+    }
+    if let Some(duration) = duration {
+        match event {
+            Event::PAUSE(interval) => *interval = parse_std_duration(duration)?,
+            Event::TIMEDPAUSE(from, to) => {
+                *to = *from + chrono::Duration::from_std(parse_std_duration(duration)?).map_err(|_| Error::BadDuration(duration.clone()))?;
+            },
+            _ => return Err(Error::InapplicableEdit { event: event.to_string(), field: "duration" })
+        }
+    }
+    if let Some(project) = project {
+        match event {
+            Event::SWITCH(_, job_id) => {
+                *job_id = JobIdentifier::parse(project);
+                job_id.require_jobtype(config)?;
+            },
+            _ => return Err(Error::InapplicableEdit { event: event.to_string(), field: "project" })
+        }
+    }
+
+    check_order(&edited, index)?;
+    check_changed_sheet(&sheet, &edited, Change::Replace, config.auto_end)?;
+
+    if verbose { println!("Changed event {} from {:?} to {:?}", index, &sheet[index], &edited[index]); }
+    store.replace(index, edited.swap_remove(index))?;
+    Ok(())
+}
+
+pub fn delete(config: &Options, index: usize) -> Result<()> {
+    let _lock = lock_sheet(&config.timesheet)?;
+    let mut store = open_store(&config.timesheet)?;
+    let sheet = store.load()?;
+    if index >= sheet.len() { return Err(Error::NoSuchEvent(index)); }
+
+    let mut changed = sheet.clone();
+    changed.remove(index);
+    check_changed_sheet(&sheet, &changed, Change::Remove(index), config.auto_end)?;
+    let removed = store.remove(index)?;
+
+    println!("Removed event {} {:?} from timesheet {}", index, removed, &config.timesheet);
+    Ok(())
+}
+
+pub fn log(config: &Options) -> Result<()> {
+    let sheet = read_sheet(&config.timesheet)?;
+
+    let mut table = Table::new();
+    table.set_titles(row![b -> "Index", b -> "Time", b -> "Event", b -> "Details"]);
+
+    for (index, event) in sheet.iter().enumerate() {
+        let time = event.time().map(|time| time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+        table.add_row(row![r -> index, time, event, event.details(config)]);
+    }
+
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    table.printstd();
+    Ok(())
 }
 
-pub fn nevermind(config: &Options) {
-    let mut sheet = read_sheet(&config.timesheet);
-    let popped = sheet.pop();
-    write_sheet(sheet, &config.timesheet);
+pub fn nevermind(config: &Options) -> Result<()> {
+    let _lock = lock_sheet(&config.timesheet)?;
+    let mut store = open_store(&config.timesheet)?;
+    let len = store.load()?.len();
+    let popped = match len {
+        0 => None,
+        _ => Some(store.remove(len - 1)?)
+    };
 
-    println!("Removed event {:?} from timesheet {}", popped, &config.timesheet)
+    println!("Removed event {:?} from timesheet {}", popped, &config.timesheet);
+    Ok(())
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Event {
     BEGIN(DateTime<Local>),
     END(DateTime<Local>),
     PAUSE(Duration),
+    /// A pause between two instants, older sheets only have the duration of PAUSE.
+    TIMEDPAUSE(DateTime<Local>, DateTime<Local>),
+    /// Start of an open-ended break, lasting until the next RESUME or END.
+    BREAK(DateTime<Local>),
+    RESUME(DateTime<Local>),
     SWITCH(DateTime<Local>, JobIdentifier)
 }
 
+impl Event {
+    /// The time the event happened at, PAUSE events only carry a duration.
+    pub fn time(&self) -> Option<DateTime<Local>> {
+        match self {
+            Self::BEGIN(time) | Self::END(time) | Self::SWITCH(time, _) | Self::TIMEDPAUSE(time, _) => Some(*time),
+            Self::BREAK(time) | Self::RESUME(time) => Some(*time),
+            Self::PAUSE(_) => None
+        }
+    }
+
+    /// What the event carries besides its kind and time, such as the length of a pause or the project switched to.
+    pub fn details(&self, config: &Options) -> String {
+        match self {
+            Self::PAUSE(interval) => format!("{}m", interval.as_secs() / 60),
+            Self::TIMEDPAUSE(from, to) => format!("{} to {}, {}m", from.format("%H:%M"), to.format("%H:%M"), (*to - *from).num_minutes()),
+            Self::SWITCH(_, job_id) => match job_id.get_jobtype(config) {
+                Some(job) => job.u_name,
+                None => format!("{} (unknown project)", job_id)
+            },
+            _ => String::new()
+        }
+    }
+}
+
 impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::BEGIN(..) => write!(f, "BEGIN"),
             Self::END(..) => write!(f, "END"),
-            Self::PAUSE(..) => write!(f, "PAUSE"),
+            Self::PAUSE(..) | Self::TIMEDPAUSE(..) => write!(f, "PAUSE"),
+            Self::BREAK(..) => write!(f, "BREAK"),
+            Self::RESUME(..) => write!(f, "RESUME"),
             Self::SWITCH(..) => write!(f, "SWITCH")
         }
     }
@@ -143,24 +560,26 @@ impl PartialEq for Event {
     fn eq(&self, other: &Self) -> bool {
         use Event::*;
         
-        match (&self, other) {
-            (BEGIN(_), BEGIN(_)) => true,
-            (END(_), END(_)) => true,
-            (PAUSE(_), PAUSE(_)) => true,
-            (SWITCH(_, _), SWITCH(_, _)) => true,
-            _ => false
-        }
+        matches!((&self, other), (BEGIN(_), BEGIN(_)) | (END(_), END(_)) | (PAUSE(_), PAUSE(_)) | (TIMEDPAUSE(..), TIMEDPAUSE(..)) | (BREAK(_), BREAK(_)) | (RESUME(_), RESUME(_)) | (SWITCH(_, _), SWITCH(_, _)))
     }
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum JobIdentifier {
     UName(String),
     InternalId(usize)
 }
 
 impl JobIdentifier {
+    /// Interprets a numeric string as an internal id and anything else as a unique name.
+    pub fn parse(identifier: &str) -> Self {
+        match identifier.parse::<usize>() {
+            Ok(id) => JobIdentifier::InternalId(id),
+            Err(_) => JobIdentifier::UName(identifier.to_owned())
+        }
+    }
+
     pub fn get_jobtype(&self, config: &Options) -> Option<JobType> {
         for job in &config.projects {
             match self {
@@ -170,18 +589,22 @@ impl JobIdentifier {
         }
         None
     }
-}
 
+    /// Like get_jobtype, but an unknown project is an error.
+    pub fn require_jobtype(&self, config: &Options) -> Result<JobType> {
+        self.get_jobtype(config).ok_or_else(|| Error::UnknownProject(self.to_string()))
+    }
 
-pub fn read_sheet(path: &str) -> Sheet {
-    let sheet_str = fs::read_to_string(path)
-        .unwrap_or(String::from("[]"));
-    let config: Sheet = serde_json::from_str(&sheet_str)
-        .expect(&format!("Timesheet file at {} with content {} was unreadable as a timesheet.", path, sheet_str));
-    config
+    pub fn get_internal_id(&self, config: &Options) -> Result<usize> {
+        Ok(self.require_jobtype(config)?.internal_id)
+    }
 }
 
-fn write_sheet(sheet: Sheet, path: &str) {
-    let sheet_str = serde_json::to_string_pretty(&sheet).unwrap();
-    fs::write(path, sheet_str).expect(&format!("Unable to write timesheet to file {}", path))
+impl fmt::Display for JobIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UName(name) => write!(f, "{}", name),
+            Self::InternalId(id) => write!(f, "{}", id)
+        }
+    }
 }
@@ -0,0 +1,165 @@
+use chrono::prelude::*;
+
+use crate::error::{Error, Result};
+use crate::events::{Sheet, SheetState, TransitionError, Event, open_days};
+use crate::options::{JobType, Options};
+use crate::views::period::midnight;
+
+
+/// Uninterrupted work on one project, the common ground of every report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkInterval {
+    /// Internal id of the project worked on.
+    pub project: usize,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    /// Indices in the sheet of the events starting, shortening and ending the interval.
+    pub source_events: Vec<usize>,
+    /// The work was ended by the auto end policy, not by an END event.
+    pub inferred: bool
+}
+
+impl WorkInterval {
+    /// The interval from start until end, if it is not empty.
+    fn between(project: usize, start: DateTime<Local>, end: DateTime<Local>, source_events: Vec<usize>, inferred: bool) -> Option<Self> {
+        (start < end).then_some(WorkInterval { project, start, end, source_events, inferred })
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        self.end - self.start
+    }
+
+    /// The part of the interval from from until to, if any.
+    pub fn within(&self, from: DateTime<Local>, to: DateTime<Local>) -> Option<WorkInterval> {
+        (self.start < to && from < self.end).then(|| WorkInterval { start: self.start.max(from), end: self.end.min(to), ..self.clone() })
+    }
+
+    /// The parts of the interval on each date it spans.
+    pub fn split_at_midnights(&self) -> Result<Vec<WorkInterval>> {
+        let mut parts = vec![];
+        let mut from = self.start;
+        while from.naive_local().date() < self.end.naive_local().date() {
+            let date = from.naive_local().date();
+            let next_day = midnight(date.succ_opt().ok_or_else(|| Error::BadDate(date.to_string()))?)?;
+            parts.push(WorkInterval { start: from, end: next_day, ..self.clone() });
+            from = next_day;
+        }
+        parts.push(WorkInterval { start: from, ..self.clone() });
+        Ok(parts)
+    }
+
+    /// The parts of the interval outside every timed pause, pauses cutting it are added to its source events.
+    fn without(self, pauses: &[(usize, DateTime<Local>, DateTime<Local>)]) -> Vec<Self> {
+        let mut parts = vec![self];
+        for &(index, from, to) in pauses {
+            parts = parts.into_iter().flat_map(|part| {
+                if to <= part.start || part.end <= from { return vec![part]; }
+
+                let mut source_events = part.source_events.clone();
+                source_events.push(index);
+                source_events.sort_unstable();
+                [
+                    WorkInterval::between(part.project, part.start, from, source_events.clone(), part.inferred),
+                    WorkInterval::between(part.project, to, part.end, source_events, part.inferred)
+                ].into_iter().flatten().collect()
+            }).collect();
+        }
+        parts
+    }
+}
+
+
+/// The intervals worked in sheet in order, and the internal id of the project worked on at the end of the sheet.
+/// Days never ended are ended by the auto end policy, and work still going on at the end of the sheet is counted until now.
+pub fn work_intervals(sheet: &Sheet, config: &Options) -> Result<(Vec<WorkInterval>, usize)> {
+    let days = open_days(sheet, config.auto_end);
+    // The day begun last was left open for good when it is past its date with no policy to end it
+    let last_begin = sheet.iter().rposition(|event| matches!(event, Event::BEGIN(_)));
+    let last_day_forgotten = days.last().zip(last_begin).is_some_and(|(day, begin)| day.index > begin);
+
+    // Inferred ends go before the event at their index
+    let mut inferred_ends = days.into_iter()
+        .filter_map(|day| config.auto_end.end_time(&day).map(|time| (day.index, time)))
+        .peekable();
+    let mut events: Vec<(usize, Event, bool)> = vec![];
+    for (index, event) in sheet.iter().enumerate() {
+        while let Some((_, time)) = inferred_ends.next_if(|(at, _)| *at == index) {
+            events.push((index, Event::END(time), true));
+        }
+        events.push((index, event.clone(), false));
+    }
+    events.extend(inferred_ends.map(|(index, time)| (index, Event::END(time), true)));
+
+    let mut intervals: Vec<WorkInterval> = vec![];
+    let mut timed_pauses: Vec<(usize, DateTime<Local>, DateTime<Local>)> = vec![];
+
+    let mut state = SheetState::default();
+    let mut project: usize = JobType::default().internal_id;
+    // Start of the work going on, untimed pauses move it later, and the events that shaped it so far
+    let mut open: Option<DateTime<Local>> = None;
+    let mut open_events: Vec<usize> = vec![];
+
+    for (index, event, inferred) in events {
+        state = match (state.transition(&event), &event, open) {
+            (Ok(next), _, _) => next,
+            (Err(TransitionError::BeginWhileBegun), Event::BEGIN(time), Some(start)) if time.naive_local().date() > start.naive_local().date() => {
+                eprintln!("Work on project {} from {} was never ended and is ignored, end it with \"tt fix\" or an auto_end policy.", project, start.format("%Y-%m-%d %H:%M"));
+                SheetState::Begun
+            },
+            (Err(error), _, _) => {
+                eprintln!("{} at event {} in timesheet, the event is ignored.", error, index);
+                continue;
+            }
+        };
+
+        match event {
+            Event::BEGIN(time) | Event::RESUME(time) => {
+                open = Some(time);
+                open_events = vec![index];
+            },
+
+            Event::SWITCH(time, job_id) => {
+                let switched_to = job_id.get_internal_id(config)?;
+                if switched_to == project { continue; }
+
+                if let Some(start) = open {
+                    open_events.push(index);
+                    intervals.extend(WorkInterval::between(project, start, time, open_events, false));
+                    open = Some(time);
+                    open_events = vec![index];
+                }
+                project = switched_to;
+            },
+
+            Event::END(time) | Event::BREAK(time) => match open.take() {
+                Some(start) => {
+                    if !inferred { open_events.push(index); }
+                    intervals.extend(WorkInterval::between(project, start, time, std::mem::take(&mut open_events), inferred));
+                },
+                // An inferred end of a day ending in a break still marks the day
+                None => if let Some(last) = intervals.last_mut().filter(|_| inferred) { last.inferred = true; }
+            },
+
+            Event::PAUSE(pause) => {
+                let pause = chrono::Duration::from_std(pause).map_err(|_| Error::BadDuration(format!("{:?}", pause)))?;
+                open = open.map(|start| start + pause);
+                open_events.push(index);
+            },
+
+            Event::TIMEDPAUSE(from, to) => timed_pauses.push((index, from, to))
+        }
+    }
+
+    if let Some(start) = open {
+        if last_day_forgotten {
+            eprintln!("Work on project {} from {} was never ended and is ignored, end it with \"tt fix\" or an auto_end policy.", project, start.format("%Y-%m-%d %H:%M"));
+        } else {
+            intervals.extend(WorkInterval::between(project, start, Local::now(), open_events, false));
+        }
+    }
+
+    let intervals = intervals.into_iter()
+        .flat_map(|interval| interval.without(&timed_pauses))
+        .collect();
+    Ok((intervals, project))
+}
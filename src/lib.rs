@@ -1,4 +1,10 @@
 pub mod options;
 pub mod events;
 pub mod views;
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+pub mod error;
+pub mod store;
+pub mod intervals;
+pub mod query;
+
+pub use error::{Error, Result};
@@ -1,12 +1,18 @@
-use clap::Parser;
-use dirs;
+use clap::{CommandFactory, Parser};
+use clap::error::ErrorKind;
 
 use std::fs;
+use std::process::exit;
 
-use timetrack::cli::{Cli, Subcommands};
-use timetrack::options::{Options, new_job, open_sheet, show_jobs};
+use timetrack::cli::{Cli, Subcommands, SheetCommands, OutputFormat, ReportView, GroupField, StorageFormat};
+use timetrack::options::{LogType, Options, new_job, open_sheet, show_jobs};
+use timetrack::{Error, Result};
 use timetrack::events;
-use timetrack::views::viewer;
+use timetrack::store::{self, SheetFormat};
+use timetrack::intervals;
+use timetrack::views::{viewer, ics, balance};
+use timetrack::views::viewer::{View, GroupBy};
+use timetrack::views::period::{Period, parse_date};
 
 
 fn main() {
@@ -24,42 +30,176 @@ fn main() {
         Some(path) => path,
         None => config_default_path
     };
-    let config_content = fs::read_to_string(config_path).unwrap_or(String::from(""));
-    let config = serde_json::from_str(&config_content).unwrap_or(Options::default());
+    let config = Options::read(&config_path).unwrap_or_else(|error| fail(error));
 
 
     if verbose { println!("Current config content: {:?}", &config); }
 
 
-    match args.subcommand {
+    if let Err(error) = run(args.subcommand, config, verbose) {
+        fail(error);
+    }
+}
+
+/// Reports error and exits, with 1 for unreadable or unwritable files and 2 for everything else.
+fn fail(error: Error) -> ! {
+    eprintln!("{}", error);
+    exit(match error {
+        Error::UnreadableSheet { .. } | Error::UnreadableConfig { .. } | Error::UnsupportedSheetVersion { .. } | Error::Serialization { .. }
+            | Error::Io { .. } | Error::Database { .. } => 1,
+        _ => 2
+    });
+}
+
+
+fn run(subcommand: Subcommands, mut config: Options, verbose: bool) -> Result<()> {
+    match subcommand {
         // Events
-        Subcommands::Begin { duration } => { events::begin(&config, &duration, verbose);}
+        Subcommands::Begin { duration, at } => events::begin(&config, &duration, &at, verbose),
 
-        Subcommands::End { duration } => { events::end(&config, &duration, verbose); }
+        Subcommands::End { duration, at } => events::end(&config, &duration, &at, verbose),
 
-        Subcommands::Pause { duration } => {
-            events::pause(&config, &duration, verbose);
-        }
+        Subcommands::Pause { duration: Some(duration), at, .. } => events::pause(&config, &duration, &at, verbose),
+
+        Subcommands::Pause { from: Some(from), to: Some(to), .. } => events::pause_between(&config, &from, &to, verbose),
+
+        Subcommands::Pause { .. } => unreachable!("A pause has either a duration or both a start and an end"),
+
+        Subcommands::Break { at } => events::take_break(&config, &at, verbose),
+
+        Subcommands::Resume { at } => events::resume(&config, &at, verbose),
+
+        Subcommands::Switch { project, at } => events::switch(&config, project, &at, verbose),
+
+        Subcommands::Nevermind => events::nevermind(&config),
 
-        Subcommands::Switch { project } => {
-            events::switch(&config, project, verbose);
+        Subcommands::Fix => events::fix(&config, verbose),
+
+        Subcommands::Edit { index, time, duration, project } => {
+            events::edit(&config, index, &time, &duration, &project, verbose)
         }
 
-        Subcommands::Nevermind => { events::nevermind(&config); }
+        Subcommands::Delete { index } => events::delete(&config, index),
 
         // Output
-        Subcommands::Show => {
-            let sheet = events::read_sheet(&config.timesheet);
-            viewer::show(sheet, &config);
+        Subcommands::Log => events::log(&config),
+
+        Subcommands::Export { output, .. } => {
+            let sheet = store::read_sheet(&config.timesheet)?;
+            let (intervals, _) = intervals::work_intervals(&sheet, &config)?;
+            let calendar = ics::render(&intervals, &config)?;
+            match output {
+                Some(path) => fs::write(&path, calendar).map_err(|source| Error::Io { path, source }),
+                None => {
+                    print!("{}", calendar);
+                    Ok(())
+                }
+            }
+        }
+
+        Subcommands::Show { from, to, week, month, last, today, day, format, output, view, group_by } => {
+            if group_by.is_some() && !matches!(view, ReportView::Week) {
+                let mut command = Cli::command();
+                command.build();
+                let show = command.find_subcommand_mut("show").expect("The show subcommand is defined");
+                show.error(ErrorKind::ArgumentConflict, "--group-by only subtotals the week view, not --view month or year").exit();
+            }
+            if let Some(format) = format {
+                config.default_output = log_type(format);
+            }
+
+            let period = if today {
+                Some(Period::today()?)
+            } else if let Some(week) = week {
+                Some(Period::week(week)?)
+            } else if let Some(month) = month {
+                Some(Period::month(month.as_deref())?)
+            } else if let Some(last) = last {
+                Some(Period::last_weeks(last)?)
+            } else if from.is_some() || to.is_some() {
+                Some(Period::between(from.as_deref(), to.as_deref())?)
+            } else {
+                None
+            };
+
+            let view = match (day, view) {
+                (Some(Some(date)), _) => View::Day(parse_date(&date)?),
+                (Some(None), _) => View::Day(chrono::Local::now().naive_local().date()),
+                (None, ReportView::Week) => View::Week,
+                (None, ReportView::Month) => View::Month,
+                (None, ReportView::Year) => View::Year
+            };
+            let sheet = match &period {
+                Some(period) => {
+                    if verbose { println!("Showing work from {} through {}", period.from.format("%Y-%m-%d"), (period.to - chrono::Duration::days(1)).format("%Y-%m-%d")); }
+                    store::read_sheet_between(&config.timesheet, period.from, period.to)?
+                },
+                None => store::read_sheet(&config.timesheet)?
+            };
+            // The flex balance of the weeks shown counts the work since the start of the schedule
+            let balance_sheet = match (&config.schedule, &period, &view, config.default_output) {
+                (Some(schedule), Some(period), View::Week, LogType::Default) => {
+                    let counted = period.back_to(schedule.since)?;
+                    Some(store::read_sheet_between(&config.timesheet, counted.from, counted.to)?)
+                },
+                _ => None
+            };
+            let group_by = group_by.map(|field| match field {
+                GroupField::Category => GroupBy::Category,
+                GroupField::ProjectId => GroupBy::ProjectId
+            });
+            viewer::show(sheet, balance_sheet, &config, view, period.as_ref(), group_by, output.as_deref())
         }
 
-        Subcommands::Projects => { show_jobs(config) }
+        Subcommands::Balance { days, format } => {
+            let schedule = config.schedule.as_ref().ok_or(Error::NoSchedule)?;
+            let sheet = store::read_sheet(&config.timesheet)?;
+            let (parsed, _) = viewer::parse_sheet(sheet, &config)?;
+            let balances = balance::balance(&balance::days_worked(&parsed), chrono::Local::now().naive_local().date(), schedule);
+            let report = match format.map_or(config.default_output, log_type) {
+                LogType::Default => balance::render_table(&balances, days)?,
+                LogType::Json => balance::render_json(&balances)?,
+                format => return Err(Error::UnsupportedView { view: String::from("balance"), format: format!("{:?}", format).to_lowercase() })
+            };
+            print!("{}", report);
+            Ok(())
+        }
+
+        Subcommands::Projects => {
+            show_jobs(config);
+            Ok(())
+        }
         
         // Manipulating config
-        Subcommands::New => { new_job(config) }
+        Subcommands::New => new_job(config),
+
+        Subcommands::Sheet { sheet_name: Some(sheet_name), .. } => open_sheet(config, &sheet_name),
 
-        Subcommands::Sheet { sheet_name } => {
-            open_sheet(config, &sheet_name);
+        Subcommands::Sheet { action: Some(SheetCommands::Convert { format }), .. } => {
+            let format = match format {
+                StorageFormat::Json => SheetFormat::Json,
+                StorageFormat::Jsonl => SheetFormat::JsonLines,
+                StorageFormat::Sqlite => SheetFormat::Sqlite
+            };
+            store::convert_sheet(&config, format)
         }
+
+        Subcommands::Sheet { .. } => {
+            let mut sheet_store = store::open_store(&config.timesheet)?;
+            let sheet = sheet_store.load()?;
+            println!("Using timesheet {} stored as {} with {} events", &config.timesheet, sheet_store.format()?, sheet.len());
+            Ok(())
+        }
+    }
+}
+
+
+fn log_type(format: OutputFormat) -> LogType {
+    match format {
+        OutputFormat::Default => LogType::Default,
+        OutputFormat::Json => LogType::Json,
+        OutputFormat::Csv => LogType::Csv,
+        OutputFormat::Markdown => LogType::Markdown,
+        OutputFormat::Html => LogType::Html
     }
 }
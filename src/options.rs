@@ -1,11 +1,14 @@
 use serde::{Serialize, Deserialize};
+use chrono::prelude::*;
 use dirs;
 use prettytable::{Table, row, format};
 
 use std::fs;
 use std::io::{self, Write};
+use std::time::Duration;
 
-use crate::events::JobIdentifier;
+use crate::error::{Error, Result};
+use crate::events::{JobIdentifier, OpenDay};
 
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -14,43 +17,118 @@ pub struct Options {
     pub default_output: LogType,
     pub projects: Vec<JobType>,
     pub current_project: String,
+    #[serde(default)]
+    pub auto_end: AutoEnd,
+    /// The flex balance is only counted when a schedule is set.
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
     config_path: String
 }
 
 impl Default for Options {
     fn default() -> Self {
+        // Without a homedir the files are placed relative to the working directory.
         let usr_path = dirs::home_dir()
-            .expect("Unable to find homedir, cannot make default config with path to timesheet.")
-            .to_str()
-            .expect("Unable to read homedir as a str, please fix.")
-            .to_owned();
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("."));
 
         Options {
-            timesheet: String::from(usr_path.clone() + "/Timesheet.time"),
+            timesheet: usr_path.clone() + "/Timesheet.time",
             default_output: LogType::default(),
             projects: vec![JobType::default()],
             current_project: JobType::default().u_name,
-            config_path: String::from(usr_path + "/.config/timetrack")
+            auto_end: AutoEnd::default(),
+            schedule: None,
+            config_path: usr_path + "/.config/timetrack"
         }
     }
 }
 
 impl Options {
-    fn save(self) {
-        let options_str = serde_json::to_string_pretty(&self).unwrap();
-        fs::write(self.config_path.clone(), options_str)
-            .expect(&format!("Unable to write configuration to file {}", self.config_path))
+    /// Reads the config file at path, the default config when there is no file.
+    pub fn read(path: &str) -> Result<Self> {
+        let options_str = match fs::read_to_string(path) {
+            Ok(options_str) => options_str,
+            Err(source) if source.kind() == io::ErrorKind::NotFound => return Ok(Options::default()),
+            Err(source) => return Err(Error::Io { path: path.to_owned(), source })
+        };
+        serde_json::from_str(&options_str).map_err(|source| Error::UnreadableConfig { path: path.to_owned(), source })
+    }
+
+    fn save(self) -> Result<()> {
+        let options_str = serde_json::to_string_pretty(&self)
+            .map_err(Error::serialization(&self.config_path))?;
+        fs::write(&self.config_path, options_str)
+            .map_err(|source| Error::Io { path: self.config_path.clone(), source })
     }
 }
 
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
 pub enum LogType {
-    Default
+    #[default]
+    Default,
+    /// One JSON document, for piping into other tools.
+    Json,
+    /// One row per day per project, for importing into payroll systems.
+    Csv,
+    /// Markdown tables, for pasting into wikis.
+    Markdown,
+    /// A self-contained HTML page, for sending by mail.
+    Html
 }
 
-impl Default for LogType {
-    fn default() -> Self { LogType::Default }
+
+/// Policy for ending days that were begun but never ended.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub enum AutoEnd {
+    /// Leave the day open, work on the last project that day is ignored.
+    #[default]
+    Never,
+    /// End the day at a fixed time of day.
+    AtTime(NaiveTime),
+    /// End the day a number of hours after it began.
+    AfterHours(f64),
+    /// End the day at the last event recorded that day.
+    LastEvent
+}
+
+impl AutoEnd {
+    /// When the policy ends the open day, not after its date unless work went on past midnight, and never before its last event.
+    pub fn end_time(&self, day: &OpenDay) -> Option<DateTime<Local>> {
+        let end_of_day = Local.from_local_datetime(&day.begun.naive_local().date().and_hms_opt(23, 59, 59)?).earliest()?;
+
+        Some(self.planned_end(day)?.min(end_of_day).max(day.last_event))
+    }
+
+    /// When the policy would end the day, never before its last event, which may be on a later date.
+    pub fn planned_end(&self, day: &OpenDay) -> Option<DateTime<Local>> {
+        let end = match self {
+            Self::Never => return None,
+            Self::AtTime(time) => Local.from_local_datetime(&day.begun.naive_local().date().and_time(*time)).earliest()?,
+            Self::AfterHours(hours) => day.begun + chrono::Duration::seconds((hours * 3600.0) as i64),
+            Self::LastEvent => day.last_event
+        };
+
+        Some(end.max(day.last_event))
+    }
+}
+
+
+/// Hours of work expected on each day of the week, for the flex balance.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Schedule {
+    /// Hours expected on each weekday, Monday first.
+    pub hours: [f64; 7],
+    /// First date counted in the flex balance, the first day with work when not given.
+    #[serde(default)]
+    pub since: Option<NaiveDate>
+}
+
+impl Schedule {
+    pub fn expected(&self, date: NaiveDate) -> Duration {
+        Duration::from_secs_f64(self.hours[date.weekday().num_days_from_monday() as usize].max(0.0) * 3600.0)
+    }
 }
 
 
@@ -76,22 +154,26 @@ impl Default for JobType {
 }
 
 
-pub fn new_job(mut config: Options) {
+fn prompt(message: &str) -> Result<String> {
+    let stdin_error = |source| Error::Io { path: String::from("stdin"), source };
+
+    print!("{}", message);
+    io::stdout().flush().map_err(stdin_error)?;
+    let mut iobuff = String::new();
+    io::stdin().read_line(&mut iobuff).map_err(stdin_error)?;
+    Ok(iobuff.trim().to_string())
+}
+
+
+pub fn new_job(mut config: Options) -> Result<()> {
     println!("Welcome to the wizard for creating a new job/project!
 A job consists of a unique name, a unique project id, a non-unique category id and a description.");
 
-    let mut iobuff: String = "".to_string();
-
     let mut u_name: String;
     let mut internal_id: usize;
     
     loop {
-        print!("Input unique name (string): ");
-        io::stdout().flush().unwrap();
-        iobuff.drain(..);
-        io::stdin().read_line(&mut iobuff).unwrap();
-        iobuff = iobuff.trim().to_string();
-        u_name = iobuff.clone();
+        u_name = prompt("Input unique name (string): ")?;
         
         if let Some(job) = JobIdentifier::UName(u_name.clone()).get_jobtype(&config) {
             eprintln!("Job with name {} already exists:", job.u_name);
@@ -102,12 +184,14 @@ A job consists of a unique name, a unique project id, a non-unique category id a
     }
     
     loop {
-        print!("Input unique project internal ID (int): ");
-        io::stdout().flush().unwrap();
-        iobuff.drain(..);
-        io::stdin().read_line(&mut iobuff).unwrap();
-        iobuff = iobuff.trim().to_string();
-        internal_id = iobuff.parse().unwrap();
+        let iobuff = prompt("Input unique project internal ID (int): ")?;
+        internal_id = match iobuff.parse() {
+            Ok(id) => id,
+            Err(_) => {
+                eprintln!("Internal id {} is not a non-negative integer.", iobuff);
+                continue;
+            }
+        };
         
         if let Some(job) = JobIdentifier::InternalId(internal_id).get_jobtype(&config) {
             eprintln!("Job with internal id {} already exists:", internal_id);
@@ -117,30 +201,13 @@ A job consists of a unique name, a unique project id, a non-unique category id a
         }
     }
 
-    print!("Input project ID (string): ");
-    io::stdout().flush().unwrap();
-    iobuff.drain(..);
-    io::stdin().read_line(&mut iobuff).unwrap();
-    iobuff = iobuff.trim().to_string();
-    let project_id : String = iobuff.clone();
-    
-    print!("Input project category (string): ");
-    io::stdout().flush().unwrap();
-    iobuff.drain(..);
-    io::stdin().read_line(&mut iobuff).unwrap();
-    iobuff = iobuff.trim().to_string();
-    let category: String = iobuff.clone();
-    
-    print!("Input descriptive string for project (string): ");
-    io::stdout().flush().unwrap();
-    iobuff.drain(..);
-    io::stdin().read_line(&mut iobuff).unwrap();
-    iobuff = iobuff.trim().to_string();
-    let description: String = iobuff.clone();
+    let project_id = prompt("Input project ID (string): ")?;
+    let category = prompt("Input project category (string): ")?;
+    let description = prompt("Input descriptive string for project (string): ")?;
     
-    let new_job = JobType{internal_id: internal_id, u_name: u_name.clone(), project_id, category, description};
+    let new_job = JobType{internal_id, u_name, project_id, category, description};
     config.projects.push(new_job);
-    config.save();
+    config.save()
 }
 
 
@@ -159,7 +226,7 @@ pub fn show_jobs(mut config: Options) {
 }
 
 
-pub fn open_sheet(mut config: Options, sheet_name: &str) {
+pub fn open_sheet(mut config: Options, sheet_name: &str) -> Result<()> {
     config.timesheet = String::from(sheet_name);
-    config.save();
+    config.save()
 }
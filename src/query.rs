@@ -0,0 +1,88 @@
+use chrono::prelude::*;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::events::{Sheet, JobIdentifier};
+use crate::intervals::{WorkInterval, work_intervals};
+use crate::options::Options;
+use crate::store::read_sheet;
+use crate::views::period::Period;
+
+
+/// The time tracked in a timesheet, for answering questions such as the hours on a project in a month.
+/// Work only counts for the part of it inside the period asked about.
+pub struct TrackedTime {
+    intervals: Vec<WorkInterval>,
+    config: Options
+}
+
+impl TrackedTime {
+    pub fn new(sheet: &Sheet, config: &Options) -> Result<Self> {
+        let (intervals, _) = work_intervals(sheet, config)?;
+        Ok(TrackedTime { intervals, config: config.clone() })
+    }
+
+    /// The time tracked in the current timesheet of config.
+    pub fn open(config: &Options) -> Result<Self> {
+        TrackedTime::new(&read_sheet(&config.timesheet)?, config)
+    }
+
+    pub fn intervals(&self) -> &[WorkInterval] {
+        &self.intervals
+    }
+
+    /// The parts of the intervals inside period.
+    pub fn within(&self, period: &Period) -> Vec<WorkInterval> {
+        self.intervals.iter()
+            .filter_map(|interval| interval.within(period.from, period.to))
+            .collect()
+    }
+
+    /// Work on the project identified by a unique name or an internal id.
+    pub fn total_for(&self, project: &str, period: &Period) -> Result<Duration> {
+        let internal_id = JobIdentifier::parse(project).get_internal_id(&self.config)?;
+        Ok(self.within(period).iter()
+            .filter(|interval| interval.project == internal_id)
+            .map(duration)
+            .sum())
+    }
+
+    /// Work per date, dates without work are left out.
+    pub fn by_day(&self, period: &Period) -> Result<BTreeMap<NaiveDate, Duration>> {
+        let mut days = BTreeMap::new();
+        for interval in self.within(period) {
+            for part in interval.split_at_midnights()? {
+                *days.entry(part.start.naive_local().date()).or_default() += duration(&part);
+            }
+        }
+        Ok(days)
+    }
+
+    /// Work per project, by internal id.
+    pub fn by_project(&self, period: &Period) -> BTreeMap<usize, Duration> {
+        let mut projects = BTreeMap::new();
+        for interval in self.within(period) {
+            *projects.entry(interval.project).or_default() += duration(&interval);
+        }
+        projects
+    }
+
+    /// Work per project category, work on projects no longer in the config counts under an empty category.
+    pub fn by_category(&self, period: &Period) -> BTreeMap<String, Duration> {
+        let mut categories = BTreeMap::new();
+        for (project, worked) in self.by_project(period) {
+            let category = JobIdentifier::InternalId(project).get_jobtype(&self.config)
+                .map(|job| job.category)
+                .unwrap_or_default();
+            *categories.entry(category).or_default() += worked;
+        }
+        categories
+    }
+}
+
+
+fn duration(interval: &WorkInterval) -> Duration {
+    interval.duration().to_std().unwrap_or_default()
+}
@@ -0,0 +1,264 @@
+use serde::{Serialize, Deserialize};
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::{SheetStore, SheetFormat, SheetMetadata, SHEET_VERSION, lock_sheet};
+use crate::error::{Error, Result};
+use crate::events::{Event, Sheet};
+
+
+/// A timesheet stored as a JSON or JSON Lines file, the file is read on first use and kept in memory.
+pub struct JsonFileStore {
+    path: String,
+    sheet_file: Option<SheetFile>
+}
+
+impl JsonFileStore {
+    pub fn open(path: &str) -> Self {
+        JsonFileStore { path: path.to_owned(), sheet_file: None }
+    }
+
+    fn sheet_file(&mut self) -> Result<&mut SheetFile> {
+        if self.sheet_file.is_none() {
+            self.sheet_file = Some(read_sheet_file(&self.path)?);
+        }
+        Ok(self.sheet_file.as_mut().expect("The sheet file was read above"))
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let path = self.path.clone();
+        write_sheet_file(self.sheet_file()?, &path)
+    }
+
+    /// Rewrites the file in format, which must be JSON or JSON Lines.
+    pub fn set_format(&mut self, format: SheetFormat) -> Result<()> {
+        self.sheet_file()?.format = format;
+        self.save()
+    }
+}
+
+impl SheetStore for JsonFileStore {
+    fn load(&mut self) -> Result<Sheet> {
+        Ok(self.sheet_file()?.events.clone())
+    }
+
+    fn append(&mut self, event: Event) -> Result<()> {
+        let has_content = fs::metadata(&self.path).is_ok_and(|file| file.len() > 0);
+        let sheet_file = self.sheet_file()?;
+
+        if sheet_file.format == SheetFormat::JsonLines && has_content {
+            sheet_file.events.push(event.clone());
+            return append_event(&self.path, &event);
+        }
+        sheet_file.events.push(event);
+        self.save()
+    }
+
+    fn insert(&mut self, index: usize, event: Event) -> Result<()> {
+        let events = &mut self.sheet_file()?.events;
+        if index > events.len() { return Err(Error::NoSuchEvent(index)); }
+
+        events.insert(index, event);
+        self.save()
+    }
+
+    fn replace(&mut self, index: usize, event: Event) -> Result<Event> {
+        let slot = self.sheet_file()?.events.get_mut(index).ok_or(Error::NoSuchEvent(index))?;
+        let replaced = std::mem::replace(slot, event);
+        self.save()?;
+        Ok(replaced)
+    }
+
+    fn remove(&mut self, index: usize) -> Result<Event> {
+        let events = &mut self.sheet_file()?.events;
+        if index >= events.len() { return Err(Error::NoSuchEvent(index)); }
+
+        let removed = events.remove(index);
+        self.save()?;
+        Ok(removed)
+    }
+
+    fn write_all(&mut self, sheet: Sheet) -> Result<()> {
+        self.sheet_file()?.events = sheet;
+        self.save()
+    }
+
+    fn format(&mut self) -> Result<SheetFormat> {
+        Ok(self.sheet_file()?.format)
+    }
+}
+
+
+/// A timesheet file, the events with a header describing the file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SheetFile {
+    pub version: u32,
+    pub metadata: SheetMetadata,
+    pub events: Sheet,
+    /// How the file is laid out on disk, detected when reading.
+    #[serde(skip)]
+    pub format: SheetFormat
+}
+
+impl SheetFile {
+    /// An empty sheet for path, stored as JSON Lines if the path ends in ".jsonl".
+    fn new(path: &str) -> Self {
+        let format = match SheetFormat::for_path(path) {
+            SheetFormat::JsonLines => SheetFormat::JsonLines,
+            _ => SheetFormat::Json
+        };
+        SheetFile { version: SHEET_VERSION, metadata: SheetMetadata::default(), events: Sheet::new(), format }
+    }
+}
+
+
+/// Header line of JSON Lines timesheets.
+#[derive(Serialize, Deserialize)]
+struct SheetHeader {
+    version: u32,
+    metadata: SheetMetadata
+}
+
+/// Upgrades for the file format, the migration at index n takes a version n + 1 sheet to version n + 2.
+const MIGRATIONS: [fn(serde_json::Value) -> serde_json::Value; 1] = [migrate_bare_array];
+
+/// Version 1 sheets are a bare array of events without a header.
+fn migrate_bare_array(events: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "version": 2,
+        "metadata": SheetMetadata::default(),
+        "events": events
+    })
+}
+
+fn sheet_version(value: &serde_json::Value) -> Option<u32> {
+    match value {
+        serde_json::Value::Array(_) => Some(1),
+        serde_json::Value::Object(header) => header.get("version")?.as_u64().map(|version| version as u32),
+        _ => None
+    }
+}
+
+
+/// Reads the timesheet at path in either format, older formats are migrated in place after the original is backed up.
+pub fn read_sheet_file(path: &str) -> Result<SheetFile> {
+    let (sheet_file, migrated_from) = parse_sheet_file(path, &read_sheet_str(path)?)?;
+    if migrated_from.is_none() { return Ok(sheet_file); }
+
+    // Read again under the lock, another tt may have migrated the sheet in the meantime
+    let _lock = lock_sheet(path)?;
+    let sheet_str = read_sheet_str(path)?;
+    let (mut sheet_file, migrated_from) = parse_sheet_file(path, &sheet_str)?;
+    if let Some(version) = migrated_from {
+        let backup_path = format!("{}.v{}.bak", path, version);
+        fs::write(&backup_path, &sheet_str).map_err(|source| Error::Io { path: backup_path.clone(), source })?;
+        write_sheet_file(&mut sheet_file, path)?;
+        eprintln!("Upgraded timesheet {} from format version {} to {}, the original is kept at {}", path, version, SHEET_VERSION, backup_path);
+    }
+    Ok(sheet_file)
+}
+
+/// The content of the file at path, empty if there is no file.
+fn read_sheet_str(path: &str) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(sheet_str) => Ok(sheet_str),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(source) => Err(Error::Io { path: path.to_owned(), source })
+    }
+}
+
+/// Parses sheet_str read from path, along with the version it was migrated from if it is in an older format.
+fn parse_sheet_file(path: &str, sheet_str: &str) -> Result<(SheetFile, Option<u32>)> {
+    if sheet_str.trim().is_empty() { return Ok((SheetFile::new(path), None)); }
+
+    let unreadable = |source| Error::UnreadableSheet { path: path.to_owned(), source };
+    let mut values = serde_json::Deserializer::from_str(sheet_str).into_iter::<serde_json::Value>();
+    let mut value = values.next().unwrap_or(Ok(serde_json::Value::Null)).map_err(unreadable)?;
+    let version = sheet_version(&value).unwrap_or(0);
+
+    if version == 0 || version > SHEET_VERSION {
+        return Err(Error::UnsupportedSheetVersion { path: path.to_owned(), version });
+    }
+
+    // A header without events is the first line of a JSON Lines sheet
+    if matches!(&value, serde_json::Value::Object(header) if !header.contains_key("events")) {
+        let header: SheetHeader = serde_json::from_value(value).map_err(unreadable)?;
+        let events = values.map(|line| serde_json::from_value(line?))
+            .collect::<std::result::Result<Sheet, _>>()
+            .map_err(unreadable)?;
+        return Ok((SheetFile { version: header.version, metadata: header.metadata, events, format: SheetFormat::JsonLines }, None));
+    }
+    if values.next().is_some() {
+        return Err(unreadable(serde::de::Error::custom("trailing content after the timesheet")));
+    }
+    if version == SHEET_VERSION {
+        return Ok((serde_json::from_value(value).map_err(unreadable)?, None));
+    }
+
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        value = migration(value);
+    }
+    Ok((serde_json::from_value(value).map_err(unreadable)?, Some(version)))
+}
+
+fn write_sheet_file(sheet_file: &mut SheetFile, path: &str) -> Result<()> {
+    sheet_file.version = SHEET_VERSION;
+    sheet_file.metadata.tool_version = String::from(env!("CARGO_PKG_VERSION"));
+
+    let sheet_str = match sheet_file.format {
+        SheetFormat::Json | SheetFormat::Sqlite => serde_json::to_string_pretty(sheet_file).map_err(Error::serialization(path))?,
+        SheetFormat::JsonLines => {
+            let header = SheetHeader { version: sheet_file.version, metadata: sheet_file.metadata.clone() };
+            let mut lines = serde_json::to_string(&header).map_err(Error::serialization(path))? + "\n";
+            for event in &sheet_file.events {
+                lines += &(event_line(event).map_err(Error::serialization(path))?);
+            }
+            lines
+        }
+    };
+    write_atomically(path, &sheet_str)
+}
+
+fn event_line(event: &Event) -> serde_json::Result<String> {
+    Ok(serde_json::to_string(event)? + "\n")
+}
+
+/// Appends event as a line to the JSON Lines sheet at path, without rewriting the file.
+fn append_event(path: &str, event: &Event) -> Result<()> {
+    let line = event_line(event).map_err(Error::serialization(path))?;
+    let mut file = fs::OpenOptions::new().append(true).open(path).map_err(Error::io(path))?;
+    file.write_all(line.as_bytes()).map_err(Error::io(path))?;
+    file.sync_all().map_err(Error::io(path))
+}
+
+/// Number of earlier versions of a timesheet kept as path.1, path.2 and so on, path.1 being the newest.
+const SHEET_BACKUPS: usize = 3;
+
+/// Writes content to a temporary file and renames it over path, so path always holds a complete sheet.
+fn write_atomically(path: &str, content: &str) -> Result<()> {
+    let temporary_path = format!("{}.{}.tmp", path, std::process::id());
+    let mut file = fs::File::create(&temporary_path).map_err(Error::io(&temporary_path))?;
+    file.write_all(content.as_bytes()).map_err(Error::io(&temporary_path))?;
+    file.sync_all().map_err(Error::io(&temporary_path))?;
+
+    rotate_backups(path)?;
+    fs::rename(&temporary_path, path).map_err(Error::io(path))
+}
+
+fn rotate_backups(path: &str) -> Result<()> {
+    if !Path::new(path).exists() { return Ok(()); }
+
+    for generation in (1..SHEET_BACKUPS).rev() {
+        let older = format!("{}.{}", path, generation);
+        if Path::new(&older).exists() {
+            let oldest = format!("{}.{}", path, generation + 1);
+            fs::rename(&older, &oldest).map_err(Error::io(&oldest))?;
+        }
+    }
+
+    let newest = format!("{}.1", path);
+    fs::copy(path, &newest).map_err(Error::io(&newest))?;
+    Ok(())
+}
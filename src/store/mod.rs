@@ -0,0 +1,276 @@
+use serde::{Serialize, Deserialize};
+use chrono::prelude::*;
+
+use std::fmt;
+use std::fs;
+use std::sync::{Mutex, MutexGuard};
+
+mod json;
+mod sqlite;
+
+pub use json::{JsonFileStore, SheetFile, read_sheet_file};
+pub use sqlite::SqliteStore;
+
+use crate::error::{Error, Result};
+use crate::events::{Event, Sheet};
+use crate::options::{Options, open_sheet};
+
+
+/// Version of the timesheet format written by this version of timetrack.
+pub const SHEET_VERSION: u32 = 2;
+
+
+/// Storage of the events in a timesheet, events are addressed by their index in sheet order.
+pub trait SheetStore {
+    /// Every event, in sheet order.
+    fn load(&mut self) -> Result<Sheet>;
+
+    /// The events happening from from until to, in sheet order.
+    /// A PAUSE without a time of its own happens at the time of the event before it.
+    fn load_range(&mut self, from: DateTime<Local>, to: DateTime<Local>) -> Result<Sheet> {
+        let sheet = self.load()?;
+        let times = effective_times(&sheet);
+
+        Ok(sheet.into_iter().zip(times)
+            .filter(|(_, time)| time.is_some_and(|time| from <= time && time < to))
+            .map(|(event, _)| event)
+            .collect())
+    }
+
+    /// The last SWITCH happening before time, which decides the project worked on at time.
+    fn last_switch_before(&mut self, time: DateTime<Local>) -> Result<Option<Event>> {
+        let sheet = self.load()?;
+        let times = effective_times(&sheet);
+
+        Ok(sheet.into_iter().zip(times)
+            .filter(|(event, at)| matches!(event, Event::SWITCH(..)) && at.is_some_and(|at| at < time))
+            .map(|(event, _)| event)
+            .next_back())
+    }
+
+    /// Time of the last BEGIN happening before time, which starts the work going on at time if there is any.
+    fn last_begin_before(&mut self, time: DateTime<Local>) -> Result<Option<DateTime<Local>>> {
+        let sheet = self.load()?;
+
+        Ok(sheet.iter()
+            .filter_map(|event| match event {
+                Event::BEGIN(at) if *at < time => Some(*at),
+                _ => None
+            })
+            .next_back())
+    }
+
+    /// Time of the first END happening at or after time, which ends the work going on at time if there is any.
+    fn first_end_from(&mut self, time: DateTime<Local>) -> Result<Option<DateTime<Local>>> {
+        let sheet = self.load()?;
+
+        Ok(sheet.iter().find_map(|event| match event {
+            Event::END(at) if *at >= time => Some(*at),
+            _ => None
+        }))
+    }
+
+    /// Time of the first BEGIN happening at or after time, which closes any day left open before time.
+    fn first_begin_from(&mut self, time: DateTime<Local>) -> Result<Option<DateTime<Local>>> {
+        let sheet = self.load()?;
+
+        Ok(sheet.iter().find_map(|event| match event {
+            Event::BEGIN(at) if *at >= time => Some(*at),
+            _ => None
+        }))
+    }
+
+    fn append(&mut self, event: Event) -> Result<()>;
+
+    /// Inserts event before the event at index, or last when index is the length of the sheet.
+    fn insert(&mut self, index: usize, event: Event) -> Result<()>;
+
+    /// Replaces the event at index, returning the event replaced.
+    fn replace(&mut self, index: usize, event: Event) -> Result<Event>;
+
+    fn remove(&mut self, index: usize) -> Result<Event>;
+
+    /// Replaces every event in the store with those in sheet.
+    fn write_all(&mut self, sheet: Sheet) -> Result<()>;
+
+    fn format(&mut self) -> Result<SheetFormat>;
+}
+
+
+/// Opens the store for the timesheet at path, SQLite databases are recognised by their extension.
+pub fn open_store(path: &str) -> Result<Box<dyn SheetStore>> {
+    match SheetFormat::for_path(path) {
+        SheetFormat::Sqlite => Ok(Box::new(SqliteStore::open(path)?)),
+        SheetFormat::Json | SheetFormat::JsonLines => Ok(Box::new(JsonFileStore::open(path)))
+    }
+}
+
+pub fn read_sheet(path: &str) -> Result<Sheet> {
+    open_store(path)?.load()
+}
+
+/// The events from from until to, led by the SWITCH to the project worked on at from.
+/// The events go back to the BEGIN of work still going on at from and on to the first BEGIN or END from to, so work and breaks
+/// going on at either end are kept whole and days left open are not taken as going on, reports clip them with WorkInterval::within.
+pub fn read_sheet_between(path: &str, from: DateTime<Local>, to: DateTime<Local>) -> Result<Sheet> {
+    let mut store = open_store(path)?;
+    let start = match store.last_begin_before(from)? {
+        Some(begun) if store.first_end_from(begun)?.is_none_or(|end| end >= from) => begun,
+        _ => from
+    };
+    let end = [store.first_begin_from(to)?, store.first_end_from(to)?].into_iter().flatten().min()
+        .map_or(to, |end| end + chrono::Duration::seconds(1));
+    let mut sheet: Sheet = store.last_switch_before(start)?.into_iter().collect();
+    sheet.extend(store.load_range(start, end)?);
+    Ok(sheet)
+}
+
+/// The time each event happens at, a PAUSE without a time happens at the time of the event before it.
+pub fn effective_times(sheet: &[Event]) -> Vec<Option<DateTime<Local>>> {
+    let mut last_time = None;
+    sheet.iter().map(|event| {
+        last_time = event.time().or(last_time);
+        last_time
+    }).collect()
+}
+
+
+/// Storage formats of timesheets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SheetFormat {
+    /// A single JSON object holding the header and every event.
+    #[default]
+    Json,
+    /// The header on the first line, followed by one event per line so events can be appended.
+    JsonLines,
+    /// An SQLite database, for querying long histories by time.
+    Sqlite
+}
+
+impl SheetFormat {
+    /// The format a new timesheet at path gets from its extension.
+    pub fn for_path(path: &str) -> Self {
+        if path.ends_with(".sqlite") || path.ends_with(".sqlite3") || path.ends_with(".db") {
+            SheetFormat::Sqlite
+        } else if path.ends_with(".jsonl") {
+            SheetFormat::JsonLines
+        } else {
+            SheetFormat::Json
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "time",
+            Self::JsonLines => "jsonl",
+            Self::Sqlite => "sqlite"
+        }
+    }
+}
+
+impl fmt::Display for SheetFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Json => write!(f, "JSON"),
+            Self::JsonLines => write!(f, "JSON Lines"),
+            Self::Sqlite => write!(f, "SQLite")
+        }
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SheetMetadata {
+    /// When the sheet got its header, for sheets from before version 2 that is when it was migrated.
+    pub created: DateTime<Local>,
+    /// Version of timetrack that last wrote the sheet.
+    pub tool_version: String
+}
+
+impl Default for SheetMetadata {
+    fn default() -> Self {
+        SheetMetadata { created: Local::now(), tool_version: String::from(env!("CARGO_PKG_VERSION")) }
+    }
+}
+
+
+/// An advisory lock on a timesheet, commands changing the sheet hold it from reading to writing.
+/// The lock is released when dropped.
+pub struct SheetLock {
+    path: String,
+    /// The locked file, None when the lock was already held by this process.
+    file: Option<fs::File>
+}
+
+impl Drop for SheetLock {
+    fn drop(&mut self) {
+        if self.file.is_some() {
+            held_locks().retain(|held| held != &self.path);
+        }
+    }
+}
+
+/// Timesheets this process holds the lock on.
+static HELD_LOCKS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn held_locks() -> MutexGuard<'static, Vec<String>> {
+    HELD_LOCKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Waits for and takes the lock on the timesheet at path, the lock lives in path.lock.
+/// Taking a lock this process already holds returns at once.
+pub fn lock_sheet(path: &str) -> Result<SheetLock> {
+    if held_locks().iter().any(|held| held == path) {
+        return Ok(SheetLock { path: path.to_owned(), file: None });
+    }
+
+    let lock_path = format!("{}.lock", path);
+    let file = fs::OpenOptions::new().create(true).write(true).truncate(false).open(&lock_path)
+        .map_err(Error::io(&lock_path))?;
+    file.lock().map_err(Error::io(&lock_path))?;
+    held_locks().push(path.to_owned());
+    Ok(SheetLock { path: path.to_owned(), file: Some(file) })
+}
+
+
+/// Rewrites the current timesheet in another storage format.
+/// JSON and JSON Lines convert in place, converting to or from SQLite writes a new file the config is pointed to.
+pub fn convert_sheet(config: &Options, format: SheetFormat) -> Result<()> {
+    let _lock = lock_sheet(&config.timesheet)?;
+    let current_format = open_store(&config.timesheet)?.format()?;
+
+    if current_format == format {
+        println!("Timesheet {} is already stored as {}", &config.timesheet, format);
+        return Ok(());
+    }
+
+    if current_format != SheetFormat::Sqlite && format != SheetFormat::Sqlite {
+        let mut store = JsonFileStore::open(&config.timesheet);
+        store.set_format(format)?;
+        println!("Converted timesheet {} to {}", &config.timesheet, format);
+        return Ok(());
+    }
+
+    let converted_path = match config.timesheet.rsplit_once('.') {
+        Some((stem, _)) if !stem.ends_with('/') => format!("{}.{}", stem, format.extension()),
+        _ => format!("{}.{}", &config.timesheet, format.extension())
+    };
+    if fs::metadata(&converted_path).is_ok_and(|file| file.len() > 0) {
+        return Err(Error::Io { path: converted_path, source: std::io::ErrorKind::AlreadyExists.into() });
+    }
+
+    let sheet = open_store(&config.timesheet)?.load()?;
+    let mut converted: Box<dyn SheetStore> = match format {
+        SheetFormat::Sqlite => Box::new(SqliteStore::open(&converted_path)?),
+        SheetFormat::Json | SheetFormat::JsonLines => {
+            let mut store = JsonFileStore::open(&converted_path);
+            store.set_format(format)?;
+            Box::new(store)
+        }
+    };
+    converted.write_all(sheet)?;
+
+    open_sheet(config.clone(), &converted_path)?;
+    println!("Converted timesheet {} to {} at {}, which is now the current timesheet", &config.timesheet, format, &converted_path);
+    Ok(())
+}
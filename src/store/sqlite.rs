@@ -0,0 +1,216 @@
+use chrono::prelude::*;
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+
+use super::{SheetStore, SheetFormat, SheetMetadata, SHEET_VERSION, effective_times};
+use crate::error::{Error, Result};
+use crate::events::{Event, Sheet};
+
+
+/// A timesheet stored in an SQLite database, each event a row indexed by the time it happens at.
+pub struct SqliteStore {
+    path: String,
+    connection: Connection
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS events (position INTEGER PRIMARY KEY, time INTEGER, event TEXT NOT NULL);
+    CREATE INDEX IF NOT EXISTS events_by_time ON events (time);
+";
+
+impl SqliteStore {
+    /// Opens the database at path, creating the tables of an empty sheet if they are missing.
+    pub fn open(path: &str) -> Result<Self> {
+        let connection = Connection::open(path).map_err(Error::database(path))?;
+        connection.execute_batch(SCHEMA).map_err(Error::database(path))?;
+
+        let version: Option<String> = connection
+            .query_row("SELECT value FROM metadata WHERE key = 'version'", [], |row| row.get(0))
+            .optional().map_err(Error::database(path))?;
+
+        match version {
+            Some(version) => {
+                let version = version.parse().unwrap_or(0);
+                if version == 0 || version > SHEET_VERSION {
+                    return Err(Error::UnsupportedSheetVersion { path: path.to_owned(), version });
+                }
+            },
+            None => {
+                let metadata = SheetMetadata::default();
+                connection.execute(
+                    "INSERT INTO metadata (key, value) VALUES ('version', ?1), ('created', ?2), ('tool_version', ?3)",
+                    params![SHEET_VERSION.to_string(), metadata.created.to_rfc3339(), metadata.tool_version]
+                ).map_err(Error::database(path))?;
+            }
+        }
+
+        Ok(SqliteStore { path: path.to_owned(), connection })
+    }
+
+    fn events(&self, query: &str, parameters: impl rusqlite::Params) -> Result<Sheet> {
+        let mut statement = self.connection.prepare(query).map_err(Error::database(&self.path))?;
+        let rows = statement.query_map(parameters, |row| row.get::<_, String>(0)).map_err(Error::database(&self.path))?;
+
+        rows.map(|row| {
+            let event = row.map_err(Error::database(&self.path))?;
+            serde_json::from_str(&event).map_err(|source| Error::UnreadableSheet { path: self.path.clone(), source })
+        }).collect()
+    }
+
+    fn len(&self) -> Result<usize> {
+        self.connection.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0)).map_err(Error::database(&self.path))
+    }
+
+    fn event_text(&self, event: &Event) -> Result<String> {
+        serde_json::to_string(event).map_err(Error::serialization(&self.path))
+    }
+
+    /// Moves the events from index on by offset, going through negative positions to keep them unique.
+    fn shift(&self, index: usize, offset: i64) -> Result<()> {
+        self.connection.execute("UPDATE events SET position = -(position + ?2) WHERE position >= ?1", params![index, offset])
+            .map_err(Error::database(&self.path))?;
+        self.connection.execute("UPDATE events SET position = -position WHERE position < 0", [])
+            .map_err(Error::database(&self.path))?;
+        Ok(())
+    }
+
+    /// Recomputes the time of the PAUSE events following a change at index, they happen at the time of the event before them.
+    fn refresh_times(&self, index: usize) -> Result<()> {
+        let mut time: Option<i64> = match index {
+            0 => None,
+            _ => self.connection.query_row("SELECT time FROM events WHERE position = ?1", params![index - 1], |row| row.get(0))
+                .optional().map_err(Error::database(&self.path))?.flatten()
+        };
+
+        let following = self.events("SELECT event FROM events WHERE position >= ?1 ORDER BY position", params![index])?;
+        for (position, event) in (index..).zip(following) {
+            match event.time() {
+                Some(_) if position > index => break,
+                Some(own_time) => time = Some(own_time.timestamp()),
+                None => {
+                    self.connection.execute("UPDATE events SET time = ?2 WHERE position = ?1", params![position, time])
+                        .map_err(Error::database(&self.path))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a transaction the statements of the store run in until it is committed, dropping it rolls them back.
+    fn transaction(&self) -> Result<Transaction<'_>> {
+        self.connection.unchecked_transaction().map_err(Error::database(&self.path))
+    }
+
+    fn record_write(&self) -> Result<()> {
+        self.connection.execute("UPDATE metadata SET value = ?1 WHERE key = 'tool_version'", params![env!("CARGO_PKG_VERSION")])
+            .map_err(Error::database(&self.path))?;
+        Ok(())
+    }
+}
+
+impl SheetStore for SqliteStore {
+    fn load(&mut self) -> Result<Sheet> {
+        self.events("SELECT event FROM events ORDER BY position", [])
+    }
+
+    fn load_range(&mut self, from: DateTime<Local>, to: DateTime<Local>) -> Result<Sheet> {
+        self.events("SELECT event FROM events WHERE time >= ?1 AND time < ?2 ORDER BY position", params![from.timestamp(), to.timestamp()])
+    }
+
+    fn last_switch_before(&mut self, time: DateTime<Local>) -> Result<Option<Event>> {
+        Ok(self.events(
+            "SELECT event FROM events WHERE time < ?1 AND event LIKE '{\"SWITCH\"%' ORDER BY position DESC LIMIT 1",
+            params![time.timestamp()]
+        )?.pop())
+    }
+
+    fn last_begin_before(&mut self, time: DateTime<Local>) -> Result<Option<DateTime<Local>>> {
+        Ok(self.events(
+            "SELECT event FROM events WHERE time < ?1 AND event LIKE '{\"BEGIN\"%' ORDER BY position DESC LIMIT 1",
+            params![time.timestamp()]
+        )?.pop().and_then(|event| event.time()))
+    }
+
+    fn first_end_from(&mut self, time: DateTime<Local>) -> Result<Option<DateTime<Local>>> {
+        Ok(self.events(
+            "SELECT event FROM events WHERE time >= ?1 AND event LIKE '{\"END\"%' ORDER BY position LIMIT 1",
+            params![time.timestamp()]
+        )?.pop().and_then(|event| event.time()))
+    }
+
+    fn first_begin_from(&mut self, time: DateTime<Local>) -> Result<Option<DateTime<Local>>> {
+        Ok(self.events(
+            "SELECT event FROM events WHERE time >= ?1 AND event LIKE '{\"BEGIN\"%' ORDER BY position LIMIT 1",
+            params![time.timestamp()]
+        )?.pop().and_then(|event| event.time()))
+    }
+
+    fn append(&mut self, event: Event) -> Result<()> {
+        let len = self.len()?;
+        self.insert(len, event)
+    }
+
+    fn insert(&mut self, index: usize, event: Event) -> Result<()> {
+        let event_text = self.event_text(&event)?;
+        let transaction = self.transaction()?;
+        if index > self.len()? { return Err(Error::NoSuchEvent(index)); }
+
+        self.shift(index, 1)?;
+        self.connection.execute(
+            "INSERT INTO events (position, time, event) VALUES (?1, ?2, ?3)",
+            params![index, event.time().map(|time| time.timestamp()), event_text]
+        ).map_err(Error::database(&self.path))?;
+        self.refresh_times(index)?;
+        self.record_write()?;
+        transaction.commit().map_err(Error::database(&self.path))
+    }
+
+    fn replace(&mut self, index: usize, event: Event) -> Result<Event> {
+        let event_text = self.event_text(&event)?;
+        let transaction = self.transaction()?;
+        let replaced = self.events("SELECT event FROM events WHERE position = ?1", params![index])?
+            .pop().ok_or(Error::NoSuchEvent(index))?;
+
+        self.connection.execute(
+            "UPDATE events SET time = ?2, event = ?3 WHERE position = ?1",
+            params![index, event.time().map(|time| time.timestamp()), event_text]
+        ).map_err(Error::database(&self.path))?;
+        self.refresh_times(index)?;
+        self.record_write()?;
+        transaction.commit().map_err(Error::database(&self.path))?;
+        Ok(replaced)
+    }
+
+    fn remove(&mut self, index: usize) -> Result<Event> {
+        let transaction = self.transaction()?;
+        let removed = self.events("SELECT event FROM events WHERE position = ?1", params![index])?
+            .pop().ok_or(Error::NoSuchEvent(index))?;
+
+        self.connection.execute("DELETE FROM events WHERE position = ?1", params![index]).map_err(Error::database(&self.path))?;
+        self.shift(index + 1, -1)?;
+        self.refresh_times(index)?;
+        self.record_write()?;
+        transaction.commit().map_err(Error::database(&self.path))?;
+        Ok(removed)
+    }
+
+    fn write_all(&mut self, sheet: Sheet) -> Result<()> {
+        let times = effective_times(&sheet);
+        let rows = sheet.iter().map(|event| self.event_text(event)).collect::<Result<Vec<_>>>()?;
+
+        let transaction = self.connection.transaction().map_err(Error::database(&self.path))?;
+        transaction.execute("DELETE FROM events", []).map_err(Error::database(&self.path))?;
+        for (position, (event, time)) in rows.into_iter().zip(times).enumerate() {
+            transaction.execute(
+                "INSERT INTO events (position, time, event) VALUES (?1, ?2, ?3)",
+                params![position, time.map(|time| time.timestamp()), event]
+            ).map_err(Error::database(&self.path))?;
+        }
+        transaction.commit().map_err(Error::database(&self.path))?;
+        self.record_write()
+    }
+
+    fn format(&mut self) -> Result<SheetFormat> {
+        Ok(SheetFormat::Sqlite)
+    }
+}
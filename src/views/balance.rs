@@ -0,0 +1,189 @@
+use serde::Serialize;
+use chrono::prelude::*;
+use prettytable::{Table, row, format};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use super::viewer::{ParsedSheet, hours_and_minutes};
+use crate::error::{Error, Result};
+use crate::options::Schedule;
+
+
+/// Work against the schedule on one day.
+pub struct DayBalance {
+    pub date: NaiveDate,
+    pub worked: Duration,
+    pub expected: Duration,
+    /// The flex balance at the end of the day, over every day counted up to and including it.
+    pub balance: chrono::Duration
+}
+
+impl DayBalance {
+    /// Overtime when positive, undertime when negative.
+    pub fn difference(&self) -> chrono::Duration {
+        signed(self.worked) - signed(self.expected)
+    }
+}
+
+
+/// Work per date in parsed, dates without work are left out.
+pub fn days_worked(parsed: &ParsedSheet) -> BTreeMap<NaiveDate, Duration> {
+    let mut days = BTreeMap::new();
+    for week in parsed {
+        let monday = week.monday();
+        for (dow, day) in week.days.iter().enumerate() {
+            if day.is_empty() { continue; }
+
+            let date = monday + chrono::Duration::days(dow as i64);
+            days.insert(date, day.values().map(|project_day| project_day.total_day).sum());
+        }
+    }
+    days
+}
+
+/// The balance of each day from the start of the schedule, or else the first day with work, through the day before today.
+/// Today counts once there is work on it.
+pub fn balance(worked: &BTreeMap<NaiveDate, Duration>, today: NaiveDate, schedule: &Schedule) -> Vec<DayBalance> {
+    let first = match schedule.since.or_else(|| worked.keys().next().copied()) {
+        Some(first) => first,
+        None => return vec![]
+    };
+    let last = match today.pred_opt() {
+        Some(yesterday) if !worked.contains_key(&today) => yesterday,
+        _ => today
+    };
+
+    let mut days = vec![];
+    let mut running = chrono::Duration::zero();
+    let mut date = first;
+    while date <= last {
+        let mut day = DayBalance {
+            date,
+            worked: worked.get(&date).copied().unwrap_or_default(),
+            expected: schedule.expected(date),
+            balance: running
+        };
+        running = running + day.difference();
+        day.balance = running;
+        days.push(day);
+        date = match date.succ_opt() {
+            Some(next) => next,
+            None => break
+        };
+    }
+    days
+}
+
+
+/// Formats duration as "+7h, 30m" or "-0h, 45m".
+pub(super) fn signed_hours_and_minutes(duration: chrono::Duration) -> String {
+    if duration < chrono::Duration::zero() {
+        format!("-{}", hours_and_minutes((-duration).to_std().unwrap_or_default()))
+    } else {
+        format!("+{}", hours_and_minutes(duration.to_std().unwrap_or_default()))
+    }
+}
+
+
+/// The balance as a table with a row per week, or a row per day followed by the week when days is set.
+pub fn render_table(balances: &[DayBalance], days: bool) -> Result<String> {
+    let mut table = Table::new();
+    table.set_titles(row!["Week", "Date", "Worked", "Expected", "Over/under", "Balance"]);
+
+    for week in balances.chunk_by(|day, next| day.date.iso_week() == next.date.iso_week()) {
+        if days {
+            for day in week {
+                table.add_row(row!["", day.date.format("%a %Y-%m-%d"), r -> hours_and_minutes(day.worked), r -> hours_and_minutes(day.expected),
+                    r -> signed_hours_and_minutes(day.difference()), r -> signed_hours_and_minutes(day.balance)]);
+            }
+        }
+
+        let (first, last) = (&week[0], &week[week.len() - 1]);
+        table.add_row(row![
+            format!("{} of {}", first.date.iso_week().week(), first.date.iso_week().year()),
+            format!("{} to {}", first.date.format("%m-%d"), last.date.format("%m-%d")),
+            r -> hours_and_minutes(week.iter().map(|day| day.worked).sum()),
+            r -> hours_and_minutes(week.iter().map(|day| day.expected).sum()),
+            r -> signed_hours_and_minutes(week_difference(week)),
+            r -> signed_hours_and_minutes(last.balance)
+        ]);
+    }
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+
+    let mut report = String::from("Using default formatting for timesheet:\n\n");
+    write!(report, "{}", table).unwrap();
+    let total = balances.last().map(|day| day.balance).unwrap_or_else(chrono::Duration::zero);
+    writeln!(report, "Flex balance: {}", signed_hours_and_minutes(total)).unwrap();
+    Ok(report)
+}
+
+
+#[derive(Serialize)]
+struct BalanceReport {
+    weeks: Vec<WeekReport>,
+    balance_minutes: i64
+}
+
+#[derive(Serialize)]
+struct WeekReport {
+    year: i32,
+    week: u32,
+    days: Vec<DayReport>,
+    worked_minutes: u64,
+    expected_minutes: u64,
+    difference_minutes: i64,
+    balance_minutes: i64
+}
+
+#[derive(Serialize)]
+struct DayReport {
+    date: NaiveDate,
+    worked_minutes: u64,
+    expected_minutes: u64,
+    difference_minutes: i64,
+    balance_minutes: i64
+}
+
+/// The balance as one JSON document.
+pub fn render_json(balances: &[DayBalance]) -> Result<String> {
+    let weeks = balances.chunk_by(|day, next| day.date.iso_week() == next.date.iso_week())
+        .map(|week| {
+            let (first, last) = (&week[0], &week[week.len() - 1]);
+            WeekReport {
+                year: first.date.iso_week().year(),
+                week: first.date.iso_week().week(),
+                days: week.iter().map(|day| DayReport {
+                    date: day.date,
+                    worked_minutes: day.worked.as_secs() / 60,
+                    expected_minutes: day.expected.as_secs() / 60,
+                    difference_minutes: day.difference().num_minutes(),
+                    balance_minutes: day.balance.num_minutes()
+                }).collect(),
+                worked_minutes: week.iter().map(|day| day.worked).sum::<Duration>().as_secs() / 60,
+                expected_minutes: week.iter().map(|day| day.expected).sum::<Duration>().as_secs() / 60,
+                difference_minutes: week_difference(week).num_minutes(),
+                balance_minutes: last.balance.num_minutes()
+            }
+        })
+        .collect();
+
+    let report = BalanceReport {
+        weeks,
+        balance_minutes: balances.last().map(|day| day.balance.num_minutes()).unwrap_or_default()
+    };
+    serde_json::to_string_pretty(&report)
+        .map(|report| report + "\n")
+        .map_err(Error::serialization("report"))
+}
+
+
+/// Overtime over the days of a week.
+pub(super) fn week_difference(week: &[DayBalance]) -> chrono::Duration {
+    week.iter().fold(chrono::Duration::zero(), |total, day| total + day.difference())
+}
+
+fn signed(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero())
+}
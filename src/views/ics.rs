@@ -0,0 +1,66 @@
+use chrono::prelude::*;
+
+use crate::error::Result;
+use crate::events::JobIdentifier;
+use crate::intervals::WorkInterval;
+use crate::options::Options;
+
+
+/// The work intervals as an iCalendar file with one VEVENT each, for seeing in a calendar where time went.
+pub fn render(intervals: &[WorkInterval], config: &Options) -> Result<String> {
+    let stamp = ics_time(Local::now());
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:-//timetrack//tt {}//EN", env!("CARGO_PKG_VERSION")),
+        String::from("CALSCALE:GREGORIAN")
+    ];
+
+    for interval in intervals {
+        let project = JobIdentifier::InternalId(interval.project).require_jobtype(config)?;
+        let mut description = format!("Project {}, ID {}, category {}.", project.u_name, project.project_id, project.category);
+        if interval.inferred {
+            description += " The end is inferred by the auto end policy.";
+        }
+
+        lines.extend([
+            String::from("BEGIN:VEVENT"),
+            format!("UID:{}-{}@timetrack", interval.start.timestamp(), interval.project),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", ics_time(interval.start)),
+            format!("DTEND:{}", ics_time(interval.end)),
+            format!("SUMMARY:{}", escape(&format!("{} ({})", project.u_name, project.project_id))),
+            format!("DESCRIPTION:{}", escape(&description)),
+            format!("CATEGORIES:{}", escape(&project.category)),
+            String::from("END:VEVENT")
+        ]);
+    }
+    lines.push(String::from("END:VCALENDAR"));
+
+    Ok(lines.iter().map(|line| fold(line)).collect::<Vec<_>>().join(""))
+}
+
+
+fn ics_time(time: DateTime<Local>) -> String {
+    time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes the characters with meaning in iCalendar text values.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Ends line with CRLF, continuing lines longer than 75 octets on the next line after a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for character in line.chars() {
+        if length + character.len_utf8() > 75 {
+            folded += "\r\n ";
+            length = 1;
+        }
+        folded.push(character);
+        length += character.len_utf8();
+    }
+    folded + "\r\n"
+}
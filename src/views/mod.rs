@@ -1,2 +1,11 @@
 pub mod viewer;
-mod show_default;
\ No newline at end of file
+pub mod period;
+pub mod ics;
+pub mod summary;
+pub mod balance;
+pub mod timeline;
+mod show_default;
+mod show_json;
+mod show_csv;
+mod show_markdown;
+mod show_html;
\ No newline at end of file
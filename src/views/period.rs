@@ -0,0 +1,83 @@
+use chrono::prelude::*;
+
+use crate::error::{Error, Result};
+
+
+/// A span of whole days to report on, from the start of the first day until the start of the day after the last.
+#[derive(Debug, Clone, Copy)]
+pub struct Period {
+    pub from: DateTime<Local>,
+    pub to: DateTime<Local>
+}
+
+impl Period {
+    /// The days from first through last.
+    pub fn days(first: NaiveDate, last: NaiveDate) -> Result<Self> {
+        if last < first { return Err(Error::EmptyInterval { from: midnight(first)?, to: midnight(last)? }); }
+        let after_last = last.succ_opt().ok_or_else(|| Error::BadDate(last.to_string()))?;
+        Ok(Period { from: midnight(first)?, to: midnight(after_last)? })
+    }
+
+    pub fn today() -> Result<Self> {
+        let today = today();
+        Period::days(today, today)
+    }
+
+    /// An ISO week of the current year, the current week when no number is given.
+    pub fn week(number: Option<u32>) -> Result<Self> {
+        let today = today();
+        let number = number.unwrap_or_else(|| today.iso_week().week());
+        let monday = NaiveDate::from_isoywd_opt(today.iso_week().year(), number, Weekday::Mon)
+            .ok_or_else(|| Error::BadDate(format!("week {}", number)))?;
+        Period::days(monday, monday + chrono::Duration::days(6))
+    }
+
+    /// A month given as "YYYY-MM", the current month when none is given.
+    pub fn month(month: Option<&str>) -> Result<Self> {
+        let first = match month {
+            Some(month) => NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").map_err(|_| Error::BadDate(month.to_owned()))?,
+            None => today().with_day(1).expect("Every month has a first day")
+        };
+        let next_first = match first.month() {
+            12 => NaiveDate::from_ymd_opt(first.year() + 1, 1, 1),
+            month => NaiveDate::from_ymd_opt(first.year(), month + 1, 1)
+        }.ok_or_else(|| Error::BadDate(first.format("%Y-%m").to_string()))?;
+        Period::days(first, next_first.pred_opt().ok_or_else(|| Error::BadDate(first.format("%Y-%m").to_string()))?)
+    }
+
+    /// The current week and the count - 1 weeks before it.
+    pub fn last_weeks(count: u32) -> Result<Self> {
+        let today = today();
+        let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+        Period::days(monday - chrono::Duration::weeks(count.saturating_sub(1) as i64), monday + chrono::Duration::days(6))
+    }
+
+    /// The days from first, or from the start of any timesheet when not given, through the last day of the period.
+    pub fn back_to(&self, first: Option<NaiveDate>) -> Result<Self> {
+        let first = first.unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).expect("1970-01-01 is a date"));
+        Ok(Period { from: midnight(first)?.min(self.from), to: self.to })
+    }
+
+    /// The days from from through to, both as "YYYY-MM-DD", a missing end leaves the period open at that end.
+    pub fn between(from: Option<&str>, to: Option<&str>) -> Result<Self> {
+        let first = from.map(parse_date).transpose()?.unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1).expect("1970-01-01 is a date"));
+        let last = to.map(parse_date).transpose()?.unwrap_or_else(|| NaiveDate::from_ymd_opt(9999, 12, 30).expect("9999-12-30 is a date"));
+        Period::days(first, last)
+    }
+}
+
+
+fn today() -> NaiveDate {
+    Local::now().naive_local().date()
+}
+
+/// Parses "YYYY-MM-DD" as a date.
+pub fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| Error::BadDate(date.to_owned()))
+}
+
+/// The start of date in local time.
+pub(crate) fn midnight(date: NaiveDate) -> Result<DateTime<Local>> {
+    date.and_hms_opt(0, 0, 0).and_then(|start| Local.from_local_datetime(&start).earliest())
+        .ok_or_else(|| Error::BadDate(date.to_string()))
+}
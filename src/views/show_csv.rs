@@ -0,0 +1,85 @@
+use std::fmt::Write;
+
+use super::viewer::{ParsedSheet, GroupBy};
+use crate::error::Result;
+use crate::events::JobIdentifier;
+use crate::options::Options;
+
+
+/// The report as CSV with one row per day per project worked on, for importing into payroll systems.
+pub fn render(parsed: ParsedSheet, group_by: Option<GroupBy>, config: &Options) -> Result<String> {
+    if let Some(group_by) = group_by {
+        return render_groups(parsed, group_by, config);
+    }
+
+    let mut rows = vec![];
+
+    for parsed_week in parsed {
+        let week = parsed_week.transpose();
+        let monday = week.monday();
+
+        for (cpid, project_week) in week.projects.iter() {
+            let project = JobIdentifier::InternalId(*cpid).require_jobtype(config)?;
+
+            for (dow, day) in project_week.days.iter().enumerate() {
+                if day.total_day.as_secs() < 60 { continue; }
+
+                let date = monday + chrono::Duration::days(dow as i64);
+                let row = [
+                    date.to_string(),
+                    project.u_name.clone(),
+                    project.project_id.clone(),
+                    project.category.clone(),
+                    (day.total_day.as_secs() / 3600).to_string(),
+                    ((day.total_day.as_secs() / 60) % 60).to_string()
+                ].iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",");
+                rows.push((date, *cpid, row));
+            }
+        }
+    }
+
+    rows.sort_by_key(|(date, cpid, _)| (*date, *cpid));
+
+    let mut report = String::from("date,u_name,project_id,category,hours,minutes\n");
+    for (_, _, row) in rows {
+        report += &row;
+        report.push('\n');
+    }
+    Ok(report)
+}
+
+
+/// The report as CSV with one row per day per category or project ID worked on.
+fn render_groups(parsed: ParsedSheet, group_by: GroupBy, config: &Options) -> Result<String> {
+    let mut report = match group_by {
+        GroupBy::Category => String::from("date,category,hours,minutes\n"),
+        GroupBy::ProjectId => String::from("date,project_id,hours,minutes\n")
+    };
+
+    for parsed_week in parsed {
+        let week = parsed_week.transpose();
+        let monday = week.monday();
+        let groups = week.groups(Some(group_by), config)?;
+
+        for dow in 0..7 {
+            let date = monday + chrono::Duration::days(dow as i64);
+            for group in groups.iter() {
+                let worked = group.work.days[dow].total_day;
+                if worked.as_secs() < 60 { continue; }
+
+                writeln!(report, "{},{},{},{}", date, csv_field(&group.key), worked.as_secs() / 3600, (worked.as_secs() / 60) % 60).unwrap();
+            }
+        }
+    }
+    Ok(report)
+}
+
+
+/// Quotes field if it holds a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
@@ -1,53 +1,122 @@
 use prettytable::{Table, Row, Cell, row, cell, format};
 
-use super::viewer::{ParsedSheet};
+use std::fmt::Write;
+use std::time::Duration;
+
+use super::balance::{DayBalance, signed_hours_and_minutes, week_difference};
+use super::viewer::{ParsedSheet, GroupBy, TransposedWeeksProjectWork};
+use crate::error::Result;
 use crate::events::JobIdentifier;
 use crate::options::Options;
 
 
-pub fn show(parsed: ParsedSheet, current_project_identifier: JobIdentifier, config: &Options) {
-    println!("Using default formatting for timesheet:");
+/// The weekly report as tables, with the work against the schedule below each week.
+pub fn render(parsed: ParsedSheet, current_project_identifier: JobIdentifier, group_by: Option<GroupBy>, balances: &[DayBalance], config: &Options) -> Result<String> {
+    let mut report = String::from("Using default formatting for timesheet:\n");
+
+    let mut any_inferred = false;
 
     for parsed_week in parsed {
         let week = parsed_week.transpose();
 
         let mut table = Table::new();
-        table.set_titles(row![H11 -> format!("Report for week {}:", week.week_number)]);
+        table.set_titles(row![H11 -> format!("Report for week {} of {}:", week.week_number, week.year)]);
         table.add_row(row!["Name", "ID", "Category", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun", "Total"]);
 
-        for (key, project_week) in week.projects.iter() {
-            let mut cell_vec: Vec<Cell> = Vec::new();
+        for group in week.groups(group_by, config)? {
+            for key in group.projects.iter() {
+                let mut cell_vec: Vec<Cell> = Vec::new();
 
-            let project = JobIdentifier::InternalId(*key).get_jobtype(config).unwrap();
+                let project = JobIdentifier::InternalId(*key).require_jobtype(config)?;
 
-            cell_vec.push(cell!(project.u_name));
-            cell_vec.push(cell!(r -> project.project_id));
-            cell_vec.push(cell!(r -> project.category));
-            let mut accumulated_project = 0;
-            for day in project_week.days.iter() {
-                cell_vec.push(cell!(r -> format!("{:>2}h, {:>2}m", day.total_day.as_secs() / 3600, (day.total_day.as_secs() / 60) % 60)));
-                accumulated_project += day.total_day.as_secs();
+                cell_vec.push(cell!(project.u_name));
+                cell_vec.push(cell!(r -> project.project_id));
+                cell_vec.push(cell!(r -> project.category));
+                let mut accumulated_project = 0;
+                for day in week.projects[key].days.iter() {
+                    cell_vec.push(cell!(r -> padded_hours_and_minutes(day.total_day, inferred_marker(day.inferred))));
+                    accumulated_project += day.total_day.as_secs();
+                    any_inferred |= day.inferred;
+                }
+                cell_vec.push(cell!(r -> padded_hours_and_minutes(Duration::from_secs(accumulated_project), "")));
+                table.add_row(Row::new(cell_vec));
+            }
+
+            if group_by.is_some() {
+                table.add_row(sum_row(format!("Subtotal {}", group.key), &group.work));
             }
-            cell_vec.push(cell!(r -> format!("{:>2}h, {:>2}m", accumulated_project / 3600, (accumulated_project / 60) % 60)));
-            table.add_row(Row::new(cell_vec));
         }
 
-        let mut cell_vec = vec![cell!(H3c -> "In total")];
-        let mut accumulated_project = 0;
-        for day in week.total.days.iter() {
-            cell_vec.push(cell!(r -> format!("{:>2}h, {:>2}m", day.total_day.as_secs() / 3600, (day.total_day.as_secs() / 60) % 60)));
-            accumulated_project += day.total_day.as_secs();
+        table.add_row(sum_row(String::from("In total"), &week.total));
+
+        let monday = week.monday();
+        let mut flex_balance = None;
+        let week_balances = &balances[balances.partition_point(|day| day.date < monday)..balances.partition_point(|day| day.date < monday + chrono::Duration::days(7))];
+        if let Some(last) = week_balances.last() {
+            let mut expected_row = vec![cell!(H3c -> "Expected")];
+            let mut difference_row = vec![cell!(H3c -> "Over/under")];
+            for dow in 0..7 {
+                match week_balances.iter().find(|day| day.date == monday + chrono::Duration::days(dow)) {
+                    Some(day) => {
+                        expected_row.push(cell!(r -> padded_hours_and_minutes(day.expected, inferred_marker(false))));
+                        difference_row.push(cell!(r -> padded_signed_hours_and_minutes(day.difference(), inferred_marker(false))));
+                    },
+                    None => {
+                        expected_row.push(cell!(""));
+                        difference_row.push(cell!(""));
+                    }
+                }
+            }
+            expected_row.push(cell!(r -> padded_hours_and_minutes(week_balances.iter().map(|day| day.expected).sum(), "")));
+            difference_row.push(cell!(r -> padded_signed_hours_and_minutes(week_difference(week_balances), "")));
+            table.add_row(Row::new(expected_row));
+            table.add_row(Row::new(difference_row));
+            flex_balance = Some(last.balance);
         }
-        cell_vec.push(cell!(r -> format!("{:>2}h, {:>2}m", accumulated_project / 3600, (accumulated_project / 60) % 60)));
-        table.add_row(Row::new(cell_vec));
 
         table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-        println!();
-        table.printstd();
-        println!("Total work: {}h, {}m", week.total_time.as_secs() / 3600, (week.total_time.as_secs() / 60) % 60);
+        report.push('\n');
+        report += &table.to_string();
+        writeln!(report, "Total work: {}h, {}m", week.total_time.as_secs() / 3600, (week.total_time.as_secs() / 60) % 60).unwrap();
+        if let Some(balance) = flex_balance {
+            writeln!(report, "Flex balance: {}", signed_hours_and_minutes(balance)).unwrap();
+        }
     }
 
-    let current_project = current_project_identifier.get_jobtype(config).unwrap();
-    println!();
-    println!("Working on project {} with ID {}, category {} and internal id {}.", current_project.u_name, current_project.project_id, current_project.category, current_project.internal_id);
+    if any_inferred {
+        report += "\n* Day was never ended, the end is inferred by the auto end policy. Run \"tt fix\" to write it to the timesheet.\n";
+    }
+
+    let current_project = current_project_identifier.require_jobtype(config)?;
+    writeln!(report, "\nWorking on project {} with ID {}, category {} and internal id {}.", current_project.u_name, current_project.project_id, current_project.category, current_project.internal_id).unwrap();
+    Ok(report)
+}
+
+
+/// A row with the label over the name columns and the work of the days summed over projects.
+fn sum_row(label: String, work: &TransposedWeeksProjectWork) -> Row {
+    let mut cell_vec = vec![cell!(H3c -> label)];
+    let mut accumulated_project = 0;
+    for day in work.days.iter() {
+        cell_vec.push(cell!(r -> padded_hours_and_minutes(day.total_day, inferred_marker(day.inferred))));
+        accumulated_project += day.total_day.as_secs();
+    }
+    cell_vec.push(cell!(r -> padded_hours_and_minutes(Duration::from_secs(accumulated_project), "")));
+    Row::new(cell_vec)
+}
+
+/// Formats duration as " 7h,  5m" followed by marker, so hours and minutes line up down the columns.
+fn padded_hours_and_minutes(duration: Duration, marker: &str) -> String {
+    format!("{:>2}h, {:>2}m{}", duration.as_secs() / 3600, (duration.as_secs() / 60) % 60, marker)
+}
+
+/// Formats duration as "+7h,  5m" or "-0h, 45m" followed by marker, lined up with padded_hours_and_minutes.
+fn padded_signed_hours_and_minutes(duration: chrono::Duration, marker: &str) -> String {
+    let (sign, magnitude) = if duration < chrono::Duration::zero() { ("-", -duration) } else { ("+", duration) };
+    let magnitude = magnitude.to_std().unwrap_or_default();
+    format!("{:>2}h, {:>2}m{}", format!("{}{}", sign, magnitude.as_secs() / 3600), (magnitude.as_secs() / 60) % 60, marker)
+}
+
+fn inferred_marker(inferred: bool) -> &'static str {
+    if inferred { "*" } else { " " }
 }
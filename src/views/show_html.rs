@@ -0,0 +1,69 @@
+use std::fmt::Write;
+
+use super::viewer::{ParsedSheet, GroupBy, hours_and_minutes};
+use crate::error::Result;
+use crate::events::JobIdentifier;
+use crate::options::Options;
+
+
+const STYLE: &str = "body { font-family: sans-serif; } \
+    table { border-collapse: collapse; } \
+    th, td { padding: 0.2em 0.6em; border-bottom: 1px solid #ccc; } \
+    td.time { text-align: right; font-variant-numeric: tabular-nums; } \
+    tr.subtotal td { font-style: italic; } \
+    tr.total td { font-weight: bold; }";
+
+/// The weekly report as a self-contained HTML page, for sending by mail.
+pub fn render(parsed: ParsedSheet, current_project_identifier: JobIdentifier, group_by: Option<GroupBy>, config: &Options) -> Result<String> {
+    let mut report = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Timesheet report</title>\n<style>{}</style>\n</head>\n<body>\n<h1>Timesheet report</h1>\n", STYLE);
+    let mut any_inferred = false;
+
+    for parsed_week in parsed {
+        let week = parsed_week.transpose();
+
+        writeln!(report, "<h2>Week {} of {}</h2>", week.week_number, week.year).unwrap();
+        report += "<table>\n<tr><th>Name</th><th>ID</th><th>Category</th><th>Mon</th><th>Tue</th><th>Wed</th><th>Thu</th><th>Fri</th><th>Sat</th><th>Sun</th><th>Total</th></tr>\n";
+
+        for group in week.groups(group_by, config)? {
+            for key in group.projects.iter() {
+                let project = JobIdentifier::InternalId(*key).require_jobtype(config)?;
+                let project_week = &week.projects[key];
+
+                write!(report, "<tr><td>{}</td><td>{}</td><td>{}</td>", escape(&project.u_name), escape(&project.project_id), escape(&project.category)).unwrap();
+                for day in project_week.days.iter() {
+                    write!(report, "<td class=\"time\">{}{}</td>", hours_and_minutes(day.total_day), if day.inferred { "*" } else { "" }).unwrap();
+                    any_inferred |= day.inferred;
+                }
+                writeln!(report, "<td class=\"time\">{}</td></tr>", hours_and_minutes(project_week.total())).unwrap();
+            }
+
+            if group_by.is_some() {
+                write!(report, "<tr class=\"subtotal\"><td colspan=\"3\">Subtotal {}</td>", escape(&group.key)).unwrap();
+                for day in group.work.days.iter() {
+                    write!(report, "<td class=\"time\">{}</td>", hours_and_minutes(day.total_day)).unwrap();
+                }
+                writeln!(report, "<td class=\"time\">{}</td></tr>", hours_and_minutes(group.work.total())).unwrap();
+            }
+        }
+
+        report += "<tr class=\"total\"><td colspan=\"3\">In total</td>";
+        for day in week.total.days.iter() {
+            write!(report, "<td class=\"time\">{}</td>", hours_and_minutes(day.total_day)).unwrap();
+        }
+        writeln!(report, "<td class=\"time\">{}</td></tr>\n</table>", hours_and_minutes(week.total_time)).unwrap();
+    }
+
+    if any_inferred {
+        report += "<p>* Day was never ended, the end is inferred by the auto end policy.</p>\n";
+    }
+
+    let current_project = current_project_identifier.require_jobtype(config)?;
+    writeln!(report, "<p>Working on project {} with ID {}, category {}.</p>", escape(&current_project.u_name), escape(&current_project.project_id), escape(&current_project.category)).unwrap();
+    report += "</body>\n</html>\n";
+    Ok(report)
+}
+
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
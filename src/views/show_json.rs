@@ -0,0 +1,143 @@
+use serde::Serialize;
+use chrono::prelude::*;
+
+use std::time::Duration;
+
+use super::viewer::{ParsedSheet, GroupBy};
+use crate::error::{Error, Result};
+use crate::events::JobIdentifier;
+use crate::options::{JobType, Options};
+
+
+#[derive(Serialize)]
+struct Report {
+    weeks: Vec<WeekReport>,
+    total_minutes: u64,
+    current_project: ProjectReport
+}
+
+#[derive(Serialize)]
+struct WeekReport {
+    year: i32,
+    week: u32,
+    days: Vec<DayReport>,
+    /// Work per project over the whole week.
+    projects: Vec<ProjectReport>,
+    /// Work per category or project ID over the whole week, when grouped.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<GroupReport>,
+    total_minutes: u64
+}
+
+#[derive(Serialize)]
+struct GroupReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project_id: Option<String>,
+    /// Internal ids of the projects in the group.
+    projects: Vec<usize>,
+    minutes: u64,
+    inferred: bool
+}
+
+#[derive(Serialize)]
+struct DayReport {
+    date: NaiveDate,
+    weekday: String,
+    projects: Vec<ProjectReport>,
+    total_minutes: u64,
+    inferred: bool
+}
+
+#[derive(Serialize)]
+struct ProjectReport {
+    internal_id: usize,
+    u_name: String,
+    project_id: String,
+    category: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minutes: Option<u64>,
+    /// Some of the work was ended by the auto end policy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inferred: Option<bool>
+}
+
+impl ProjectReport {
+    fn new(project: JobType, worked: Option<(Duration, bool)>) -> Self {
+        ProjectReport {
+            internal_id: project.internal_id,
+            u_name: project.u_name,
+            project_id: project.project_id,
+            category: project.category,
+            minutes: worked.map(|(worked, _)| minutes(worked)),
+            inferred: worked.map(|(_, inferred)| inferred)
+        }
+    }
+}
+
+
+/// The report as one JSON document, for piping into other tools.
+pub fn render(parsed: ParsedSheet, current_project_identifier: JobIdentifier, group_by: Option<GroupBy>, config: &Options) -> Result<String> {
+    let mut weeks = vec![];
+    let mut total = Duration::from_secs(0);
+
+    for parsed_week in parsed {
+        let monday = parsed_week.monday();
+        let mut days = vec![];
+
+        for (dow, day) in parsed_week.days.iter().enumerate() {
+            if day.is_empty() { continue; }
+
+            let date = monday + chrono::Duration::days(dow as i64);
+            let projects = day.iter()
+                .map(|(cpid, project_day)| Ok(ProjectReport::new(JobIdentifier::InternalId(*cpid).require_jobtype(config)?, Some((project_day.total_day, project_day.inferred)))))
+                .collect::<Result<Vec<_>>>()?;
+            days.push(DayReport {
+                date,
+                weekday: date.weekday().to_string(),
+                total_minutes: minutes(day.values().map(|project_day| project_day.total_day).sum()),
+                inferred: day.values().any(|project_day| project_day.inferred),
+                projects
+            });
+        }
+
+        let week = parsed_week.transpose();
+        let projects = week.projects.iter()
+            .map(|(cpid, project_week)| {
+                let worked = project_week.days.iter().map(|day| day.total_day).sum();
+                let inferred = project_week.days.iter().any(|day| day.inferred);
+                Ok(ProjectReport::new(JobIdentifier::InternalId(*cpid).require_jobtype(config)?, Some((worked, inferred))))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let groups = match group_by {
+            Some(group_by) => week.groups(Some(group_by), config)?.into_iter()
+                .map(|group| GroupReport {
+                    category: (group_by == GroupBy::Category).then(|| group.key.clone()),
+                    project_id: (group_by == GroupBy::ProjectId).then(|| group.key.clone()),
+                    minutes: minutes(group.work.total()),
+                    inferred: group.work.days.iter().any(|day| day.inferred),
+                    projects: group.projects
+                })
+                .collect(),
+            None => vec![]
+        };
+
+        total += week.total_time;
+        weeks.push(WeekReport { year: week.year, week: week.week_number, days, projects, groups, total_minutes: minutes(week.total_time) });
+    }
+
+    let report = Report {
+        weeks,
+        total_minutes: minutes(total),
+        current_project: ProjectReport::new(current_project_identifier.require_jobtype(config)?, None)
+    };
+    serde_json::to_string_pretty(&report)
+        .map(|report| report + "\n")
+        .map_err(Error::serialization("report"))
+}
+
+
+fn minutes(duration: Duration) -> u64 {
+    duration.as_secs() / 60
+}
@@ -0,0 +1,63 @@
+use std::fmt::Write;
+
+use super::viewer::{ParsedSheet, GroupBy, hours_and_minutes};
+use crate::error::Result;
+use crate::events::JobIdentifier;
+use crate::options::Options;
+
+
+/// The weekly report as Markdown tables, for pasting into wikis.
+pub fn render(parsed: ParsedSheet, current_project_identifier: JobIdentifier, group_by: Option<GroupBy>, config: &Options) -> Result<String> {
+    let mut report = String::from("# Timesheet report\n");
+    let mut any_inferred = false;
+
+    for parsed_week in parsed {
+        let week = parsed_week.transpose();
+
+        writeln!(report, "\n## Week {} of {}\n", week.week_number, week.year).unwrap();
+        report += "| Name | ID | Category | Mon | Tue | Wed | Thu | Fri | Sat | Sun | Total |\n";
+        report += "|:-----|---:|---------:|----:|----:|----:|----:|----:|----:|----:|------:|\n";
+
+        for group in week.groups(group_by, config)? {
+            for key in group.projects.iter() {
+                let project = JobIdentifier::InternalId(*key).require_jobtype(config)?;
+                let project_week = &week.projects[key];
+
+                write!(report, "| {} | {} | {} |", escape(&project.u_name), escape(&project.project_id), escape(&project.category)).unwrap();
+                for day in project_week.days.iter() {
+                    write!(report, " {}{} |", hours_and_minutes(day.total_day), if day.inferred { "\\*" } else { "" }).unwrap();
+                    any_inferred |= day.inferred;
+                }
+                writeln!(report, " {} |", hours_and_minutes(project_week.total())).unwrap();
+            }
+
+            if group_by.is_some() {
+                write!(report, "| *Subtotal {}* | | |", escape(&group.key)).unwrap();
+                for day in group.work.days.iter() {
+                    write!(report, " *{}* |", hours_and_minutes(day.total_day)).unwrap();
+                }
+                writeln!(report, " *{}* |", hours_and_minutes(group.work.total())).unwrap();
+            }
+        }
+
+        report += "| **In total** | | |";
+        for day in week.total.days.iter() {
+            write!(report, " **{}** |", hours_and_minutes(day.total_day)).unwrap();
+        }
+        writeln!(report, " **{}** |", hours_and_minutes(week.total_time)).unwrap();
+    }
+
+    if any_inferred {
+        report += "\n\\* Day was never ended, the end is inferred by the auto end policy.\n";
+    }
+
+    let current_project = current_project_identifier.require_jobtype(config)?;
+    writeln!(report, "\nWorking on project {} with ID {}, category {}.", escape(&current_project.u_name), escape(&current_project.project_id), escape(&current_project.category)).unwrap();
+    Ok(report)
+}
+
+
+/// Escapes the characters that would break a table cell.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('|', "\\|").replace('*', "\\*").replace('\n', " ")
+}
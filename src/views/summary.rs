@@ -0,0 +1,188 @@
+use serde::Serialize;
+use chrono::prelude::*;
+use prettytable::{Table, row, format};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::time::Duration;
+
+use super::viewer::hours_and_minutes;
+use crate::error::{Error, Result};
+use crate::events::JobIdentifier;
+use crate::intervals::WorkInterval;
+use crate::options::Options;
+
+
+/// Length of the periods a summary covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummarySpan {
+    Month,
+    Year
+}
+
+
+/// Work summed over a month or a year.
+pub struct Summary {
+    pub year: i32,
+    /// The month, counted from 1, or None for a summary of the whole year.
+    pub month: Option<u32>,
+    pub projects: BTreeMap<usize, SummaryLine>,
+    pub categories: BTreeMap<String, SummaryLine>,
+    pub total: SummaryLine
+}
+
+#[derive(Default)]
+pub struct SummaryLine {
+    pub worked: Duration,
+    /// Dates with any work counted in the line.
+    pub days: BTreeSet<NaiveDate>
+}
+
+impl SummaryLine {
+    fn add(&mut self, date: NaiveDate, worked: Duration) {
+        self.worked += worked;
+        self.days.insert(date);
+    }
+
+    /// Work per worked day.
+    pub fn average(&self) -> Duration {
+        match self.days.len() {
+            0 => Duration::from_secs(0),
+            days => self.worked / days as u32
+        }
+    }
+}
+
+impl Summary {
+    fn label(&self) -> String {
+        match self.month {
+            Some(month) => NaiveDate::from_ymd_opt(self.year, month, 1).expect("Summaries are of months with work").format("%B %Y").to_string(),
+            None => self.year.to_string()
+        }
+    }
+}
+
+
+/// Sums the intervals per month or year, per project and per category, work crossing midnight counts on each day it spans.
+pub fn summarize(intervals: &[WorkInterval], span: SummarySpan, config: &Options) -> Result<Vec<Summary>> {
+    let mut summaries: BTreeMap<(i32, Option<u32>), Summary> = BTreeMap::new();
+
+    for interval in intervals {
+        let category = JobIdentifier::InternalId(interval.project).require_jobtype(config)?.category;
+
+        for part in interval.split_at_midnights()? {
+            let date = part.start.naive_local().date();
+            let month = match span {
+                SummarySpan::Month => Some(date.month()),
+                SummarySpan::Year => None
+            };
+            let worked = part.duration().to_std().unwrap_or_default();
+
+            let summary = summaries.entry((date.year(), month)).or_insert_with(|| Summary {
+                year: date.year(),
+                month,
+                projects: BTreeMap::new(),
+                categories: BTreeMap::new(),
+                total: SummaryLine::default()
+            });
+            summary.projects.entry(part.project).or_default().add(date, worked);
+            summary.categories.entry(category.clone()).or_default().add(date, worked);
+            summary.total.add(date, worked);
+        }
+    }
+
+    Ok(summaries.into_values().collect())
+}
+
+
+/// The summaries as tables, one for the projects and one for the categories of each period.
+pub fn render_table(summaries: &[Summary], config: &Options) -> Result<String> {
+    let mut report = String::from("Using default formatting for timesheet:\n");
+
+    for summary in summaries {
+        let mut table = Table::new();
+        table.set_titles(row![H6 -> format!("Report for {}:", summary.label())]);
+        table.add_row(row!["Name", "ID", "Category", "Total", "Days", "Per day"]);
+        for (key, line) in summary.projects.iter() {
+            let project = JobIdentifier::InternalId(*key).require_jobtype(config)?;
+            table.add_row(row![project.u_name, r -> project.project_id, r -> project.category, r -> hours_and_minutes(line.worked), r -> line.days.len(), r -> hours_and_minutes(line.average())]);
+        }
+        table.add_row(row![H3c -> "In total", r -> hours_and_minutes(summary.total.worked), r -> summary.total.days.len(), r -> hours_and_minutes(summary.total.average())]);
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+
+        let mut categories = Table::new();
+        categories.set_titles(row!["Category", "Total", "Days", "Per day"]);
+        for (category, line) in summary.categories.iter() {
+            categories.add_row(row![category, r -> hours_and_minutes(line.worked), r -> line.days.len(), r -> hours_and_minutes(line.average())]);
+        }
+        categories.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+
+        writeln!(report, "\n{}\n{}", table, categories).unwrap();
+    }
+    Ok(report)
+}
+
+
+#[derive(Serialize)]
+struct SummaryReport {
+    year: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    month: Option<u32>,
+    projects: Vec<LineReport>,
+    categories: Vec<LineReport>,
+    total: LineReport
+}
+
+#[derive(Serialize)]
+struct LineReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    internal_id: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    u_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    minutes: u64,
+    worked_days: usize,
+    average_minutes: u64
+}
+
+impl LineReport {
+    fn new(line: &SummaryLine) -> Self {
+        LineReport {
+            internal_id: None,
+            u_name: None,
+            project_id: None,
+            category: None,
+            minutes: line.worked.as_secs() / 60,
+            worked_days: line.days.len(),
+            average_minutes: line.average().as_secs() / 60
+        }
+    }
+}
+
+/// The summaries as one JSON document.
+pub fn render_json(summaries: &[Summary], config: &Options) -> Result<String> {
+    let reports = summaries.iter().map(|summary| {
+        let projects = summary.projects.iter().map(|(key, line)| {
+            let project = JobIdentifier::InternalId(*key).require_jobtype(config)?;
+            Ok(LineReport {
+                internal_id: Some(project.internal_id),
+                u_name: Some(project.u_name),
+                project_id: Some(project.project_id),
+                category: Some(project.category),
+                ..LineReport::new(line)
+            })
+        }).collect::<Result<Vec<_>>>()?;
+        let categories = summary.categories.iter()
+            .map(|(category, line)| LineReport { category: Some(category.clone()), ..LineReport::new(line) })
+            .collect();
+
+        Ok(SummaryReport { year: summary.year, month: summary.month, projects, categories, total: LineReport::new(&summary.total) })
+    }).collect::<Result<Vec<_>>>()?;
+
+    serde_json::to_string_pretty(&reports)
+        .map(|report| report + "\n")
+        .map_err(Error::serialization("report"))
+}
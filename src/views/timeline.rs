@@ -0,0 +1,142 @@
+use serde::Serialize;
+use chrono::prelude::*;
+use prettytable::{Table, row, format};
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use super::period::midnight;
+use super::viewer::hours_and_minutes;
+use crate::error::{Error, Result};
+use crate::events::{Sheet, JobIdentifier};
+use crate::intervals::work_intervals;
+use crate::options::Options;
+use crate::store::effective_times;
+
+
+/// One line of the timeline of a day, an event or a stretch of work ending without one.
+#[derive(Serialize)]
+pub struct TimelineRow {
+    pub time: DateTime<Local>,
+    /// Index of the event in the sheet, None for ends that are not in the sheet.
+    pub index: Option<usize>,
+    pub event: String,
+    pub details: String,
+    /// The stretch of work ending at this line.
+    pub stretch: Option<Stretch>
+}
+
+#[derive(Serialize)]
+pub struct Stretch {
+    pub project: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub minutes: u64,
+    /// Work on the day up to and including the stretch.
+    pub total_minutes: u64
+}
+
+
+/// The events on date in order, with the stretch of work each of them ends and the running total of the day.
+/// Work carried over from the day before starts the day at midnight.
+pub fn timeline(sheet: &Sheet, date: NaiveDate, config: &Options) -> Result<Vec<TimelineRow>> {
+    let next_date = date.succ_opt().ok_or_else(|| Error::BadDate(date.to_string()))?;
+    let (day_start, day_end) = (midnight(date)?, midnight(next_date)?);
+
+    let mut rows: Vec<TimelineRow> = sheet.iter().zip(effective_times(sheet)).enumerate()
+        .filter(|(_, (_, time))| time.is_some_and(|time| day_start <= time && time < day_end))
+        .map(|(index, (event, time))| TimelineRow {
+            time: time.expect("Events without a time are filtered out"),
+            index: Some(index),
+            event: event.to_string(),
+            details: event.details(config),
+            stretch: None
+        })
+        .collect();
+
+    let (intervals, _) = work_intervals(sheet, config)?;
+    for interval in intervals.iter().filter(|interval| interval.start < day_end && day_start < interval.end) {
+        let stretch = Stretch {
+            project: JobIdentifier::InternalId(interval.project).require_jobtype(config)?.u_name,
+            start: interval.start.max(day_start),
+            end: interval.end.min(day_end),
+            minutes: 0,
+            total_minutes: 0
+        };
+
+        // The stretch goes on the line of the event ending it, if it ends on the day by an event
+        let ending_row = rows.iter_mut().find(|row| {
+            row.index.is_some_and(|index| interval.source_events.contains(&index)) && row.time == interval.end && row.stretch.is_none()
+        });
+        match ending_row {
+            Some(row) => row.stretch = Some(stretch),
+            None => {
+                let event = if interval.end >= day_end {
+                    "MIDNIGHT"
+                } else if interval.inferred {
+                    "END*"
+                } else {
+                    "NOW"
+                };
+                rows.push(TimelineRow { time: stretch.end, index: None, event: event.to_owned(), details: String::new(), stretch: Some(stretch) });
+            }
+        }
+    }
+
+    rows.sort_by_key(|row| (row.time, row.index.is_none()));
+
+    let mut total = chrono::Duration::zero();
+    for stretch in rows.iter_mut().filter_map(|row| row.stretch.as_mut()) {
+        total = total + (stretch.end - stretch.start);
+        stretch.minutes = (stretch.end - stretch.start).num_minutes() as u64;
+        stretch.total_minutes = total.num_minutes() as u64;
+    }
+    Ok(rows)
+}
+
+
+/// The timeline as a table.
+pub fn render_table(rows: &[TimelineRow], date: NaiveDate) -> Result<String> {
+    let mut table = Table::new();
+    table.set_titles(row![H7 -> format!("Timeline for {}:", date.format("%A %Y-%m-%d"))]);
+    table.add_row(row!["Time", "Event", "Details", "Project", "Stretch", "Duration", "Day total"]);
+
+    for row in rows {
+        match &row.stretch {
+            Some(stretch) => table.add_row(row![
+                clock(row), row.event, row.details, stretch.project,
+                format!("{} to {}", stretch.start.format("%H:%M"), clock(row)),
+                r -> hours_and_minutes(minutes(stretch.minutes)), r -> hours_and_minutes(minutes(stretch.total_minutes))
+            ]),
+            None => table.add_row(row![clock(row), row.event, row.details, "", "", "", ""])
+        };
+    }
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+
+    let mut report = String::from("Using default formatting for timesheet:\n\n");
+    write!(report, "{}", table).unwrap();
+    if rows.iter().any(|row| row.event == "END*") {
+        report += "\n* Day was never ended, the end is inferred by the auto end policy. Run \"tt fix\" to write it to the timesheet.\n";
+    }
+    Ok(report)
+}
+
+/// The timeline as one JSON document.
+pub fn render_json(rows: &[TimelineRow]) -> Result<String> {
+    serde_json::to_string_pretty(rows)
+        .map(|report| report + "\n")
+        .map_err(Error::serialization("report"))
+}
+
+
+/// Time of day of the row, the end of the day is shown as 24:00.
+fn clock(row: &TimelineRow) -> String {
+    match row.event.as_str() {
+        "MIDNIGHT" => String::from("24:00"),
+        _ => row.time.format("%H:%M").to_string()
+    }
+}
+
+fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
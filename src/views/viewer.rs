@@ -1,186 +1,200 @@
 use chrono::prelude::*;
 
+use std::fs;
 use std::time::Duration;
 use std::collections::BTreeMap;
 
-use super::show_default;
-use crate::events::{Sheet, Event, JobIdentifier};
-use crate::options::{LogType, JobType, Options};
+use super::{show_default, show_json, show_csv, show_markdown, show_html};
+use super::balance::{DayBalance, balance, days_worked};
+use super::summary::{self, SummarySpan, summarize};
+use super::period::Period;
+use super::timeline::{self, timeline};
+use crate::error::{Error, Result};
+use crate::events::{Sheet, JobIdentifier};
+use crate::intervals::{WorkInterval, work_intervals};
+use crate::options::{LogType, Options};
 
 
 pub type ParsedSheet = Vec<WeeksWork>;
 pub type DaysWork = BTreeMap<usize, DaysProjectWork>;
 
 
-pub fn show(sheet: Sheet, config: &Options) {
-    let (parsed, project_identifier) = parse_sheet(sheet, config);
+/// The periods tt show sums work over.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum View {
+    #[default]
+    Week,
+    Month,
+    Year,
+    /// The timeline of the events on a single date.
+    Day(NaiveDate)
+}
 
-    match config.default_output {
-        LogType::Default => show_default::show(parsed, project_identifier, config)
+impl View {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Year => "year",
+            Self::Day(_) => "day"
+        }
     }
 }
 
+/// Project attribute the week view subtotals the work by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Category,
+    ProjectId
+}
 
-fn parse_sheet(sheet: Sheet, config: &Options) -> (ParsedSheet, JobIdentifier) {
-    let mut parsed_sheet: ParsedSheet = vec![];
-
-    let mut current_week_work: WeeksWork = WeeksWork::default();
-    let mut current_day_work: DaysWork = DaysWork::default();
-
-    let mut last_dow: usize = 0; // Last parsed day of week
-    let mut cpid: usize = JobType::default().internal_id; // Current logical ID
-
-    
-    // Handler logic for allowing event switching before first begin in sheet
-    let mut begun: bool = false;
-
-    for event in sheet {
-        match (event, begun) {
-            (Event::BEGIN(time), false) => {
-                begun = true;
-                current_week_work.week_number = time.iso_week().week();
-                last_dow = time.weekday().num_days_from_monday() as usize;
-                current_day_work.insert(cpid, DaysProjectWork { total_day: Duration::from_secs(0), start: Some(time) });
-            },
-
-            (Event::BEGIN(time), true) => {
-
-                if time.iso_week().week() != current_week_work.week_number {
-                    if current_day_work.get(&cpid).unwrap().total_day == Duration::from_secs(0) {
-                        eprintln!("Work was not finished on project {} last week, work on that project that day is ignored.", cpid);
-                    }
-
-                    current_week_work.days[last_dow] = current_day_work;
-                    parsed_sheet.push(current_week_work);
-                    
-                    current_week_work = WeeksWork::default();
-                    current_week_work.week_number = time.iso_week().week();
-                    current_day_work = DaysWork::default();
-                    current_day_work.insert(cpid, DaysProjectWork { total_day: Duration::from_secs(0), start: Some(time) });
-
-                    last_dow = time.weekday().num_days_from_monday() as usize;
-
-                } else if time.weekday().num_days_from_monday() as usize != last_dow {
-                    if current_day_work.get(&cpid).unwrap().total_day == Duration::from_secs(0) {
-                        eprintln!("Work was not finished on project {} last day, work on that project that day is ignored.", cpid);
-                    }
-
-                    current_week_work.days[last_dow] = current_day_work;
-                    current_day_work = DaysWork::default();
-                    current_day_work.insert(cpid, DaysProjectWork { total_day: Duration::from_secs(0), start: Some(time) });
-
-                    last_dow = time.weekday().num_days_from_monday() as usize;
-
-                } else {
-                    current_day_work.get_mut(&cpid).unwrap().start = Some(time - chrono::Duration::from_std(current_day_work.get(&cpid).unwrap().total_day).unwrap());
-                }
-            },
-
-            (Event::SWITCH(_, job_id), false) => {
-                cpid = job_id.get_jobtype(config).unwrap().internal_id;
-            },
-
-            (Event::SWITCH(time, job_id), true) => {
-                if job_id.get_jobtype(config).unwrap().internal_id == cpid { continue; }
-
-                if time.iso_week().week() != current_week_work.week_number {
-                    if current_day_work.get(&cpid).unwrap().total_day == Duration::from_secs(0) {
-                        eprintln!("Work was not finished on project {} last week, work on that project that day is ignored.", cpid);
-                    }
-
-                    current_week_work.days[last_dow] = current_day_work;
-                    parsed_sheet.push(current_week_work);
-
-                    cpid = job_id.get_jobtype(config).unwrap().internal_id;
-                    
-                    current_week_work = WeeksWork::default();
-                    current_week_work.week_number = time.iso_week().week();
-                    current_day_work = DaysWork::default();
-                    current_day_work.insert(cpid, DaysProjectWork { total_day: Duration::from_secs(0), start: Some(time) });
-
-                    last_dow = time.weekday().num_days_from_monday() as usize;
-
-                } else if time.weekday().num_days_from_monday() as usize != last_dow {
-                    if current_day_work.get(&cpid).unwrap().total_day == Duration::from_secs(0) {
-                        eprintln!("Work was not finished on project {} last day, work on that project that day is ignored.", cpid);
-                    }
-
-                    current_week_work.days[last_dow] = current_day_work;
 
-                    cpid = job_id.get_jobtype(config).unwrap().internal_id;
+/// Writes the report on sheet in the default_output format of config, to the file at output or else to stdout.
+/// Only work inside period counts when one is given, the week view subtotals the projects by group_by if given.
+/// The flex balance is counted over balance_sheet, which reaches back to the start of the schedule, or else over sheet.
+pub fn show(sheet: Sheet, balance_sheet: Option<Sheet>, config: &Options, view: View, period: Option<&Period>, group_by: Option<GroupBy>, output: Option<&str>) -> Result<()> {
+    let report = match view {
+        View::Week => {
+            let (intervals, current_project) = intervals_within(&sheet, period, config)?;
+            let (parsed, project_identifier) = (parse_intervals(&intervals)?, JobIdentifier::InternalId(current_project));
+            match config.default_output {
+                LogType::Default => {
+                    let balances = flex_balances(balance_sheet.as_ref().unwrap_or(&sheet), period, config)?;
+                    show_default::render(parsed, project_identifier, group_by, &balances, config)?
+                },
+                LogType::Json => show_json::render(parsed, project_identifier, group_by, config)?,
+                LogType::Csv => show_csv::render(parsed, group_by, config)?,
+                LogType::Markdown => show_markdown::render(parsed, project_identifier, group_by, config)?,
+                LogType::Html => show_html::render(parsed, project_identifier, group_by, config)?
+            }
+        },
+        View::Month | View::Year => {
+            let span = if matches!(view, View::Month) { SummarySpan::Month } else { SummarySpan::Year };
+            let summaries = summarize(&intervals_within(&sheet, period, config)?.0, span, config)?;
+            match config.default_output {
+                LogType::Default => summary::render_table(&summaries, config)?,
+                LogType::Json => summary::render_json(&summaries, config)?,
+                format => return Err(Error::UnsupportedView { view: view.name().to_owned(), format: format!("{:?}", format).to_lowercase() })
+            }
+        },
+        View::Day(date) => {
+            let rows = timeline(&sheet, date, config)?;
+            match config.default_output {
+                LogType::Default => timeline::render_table(&rows, date)?,
+                LogType::Json => timeline::render_json(&rows)?,
+                format => return Err(Error::UnsupportedView { view: view.name().to_owned(), format: format!("{:?}", format).to_lowercase() })
+            }
+        }
+    };
 
-                    current_day_work = DaysWork::default();
-                    current_day_work.insert(cpid, DaysProjectWork { total_day: Duration::from_secs(0), start: Some(time) });
+    match output {
+        Some(path) => fs::write(path, report).map_err(Error::io(path)),
+        None => {
+            print!("{}", report);
+            Ok(())
+        }
+    }
+}
 
-                    last_dow = time.weekday().num_days_from_monday() as usize;
 
-                } else {
-                    current_day_work.get_mut(&cpid).unwrap().total_day = (time - current_day_work.get(&cpid).unwrap().start.unwrap()).to_std().unwrap();
-
-                    cpid = job_id.get_jobtype(config).unwrap().internal_id;
-
-                    if let Some(sofar) = current_day_work.get_mut(&cpid) {
-                        if sofar.total_day == Duration::from_secs(0) {
-                            eprintln!("Work was not finished on project {} this day, work on this project today is ignored.", cpid);
-                        }
-                        sofar.start = Some(time - chrono::Duration::from_std(sofar.total_day).unwrap());
-                        sofar.total_day = Duration::from_secs(0);
-                    } else {
-                        current_day_work.insert(cpid, DaysProjectWork { total_day: Duration::from_secs(0), start: Some(time) });
-                    }
-                }
-            }
+/// The work intervals of sheet clipped to period, and the project worked on at the end of the sheet.
+fn intervals_within(sheet: &Sheet, period: Option<&Period>, config: &Options) -> Result<(Vec<WorkInterval>, usize)> {
+    let (intervals, current_project) = work_intervals(sheet, config)?;
+    let intervals = match period {
+        Some(period) => intervals.iter().filter_map(|interval| interval.within(period.from, period.to)).collect(),
+        None => intervals
+    };
+    Ok((intervals, current_project))
+}
 
-            (Event::END(time), true) => {
-                if time.iso_week().week() != current_week_work.week_number || time.weekday().num_days_from_monday() as usize != last_dow {
-                    panic!("Illegal end on start of new day")
-                } else {
-                    current_day_work.get_mut(&cpid).unwrap().total_day = (time - current_day_work.get(&cpid).unwrap().start.unwrap()).to_std().unwrap();
-                }
-            },
+/// The flex balance of each day in period, or of every day without one, counted over the work in sheet.
+/// There are none without a schedule in config.
+fn flex_balances(sheet: &Sheet, period: Option<&Period>, config: &Options) -> Result<Vec<DayBalance>> {
+    let schedule = match &config.schedule {
+        Some(schedule) => schedule,
+        None => return Ok(vec![])
+    };
+    let parsed = parse_intervals(&work_intervals(sheet, config)?.0)?;
+    let balances = balance(&days_worked(&parsed), Local::now().naive_local().date(), schedule);
+    Ok(match period {
+        Some(period) => {
+            let (first, end) = (period.from.naive_local().date(), period.to.naive_local().date());
+            balances.into_iter().filter(|day| first <= day.date && day.date < end).collect()
+        },
+        None => balances
+    })
+}
 
-            (Event::PAUSE(interval), true) => {
-                let project_start = current_day_work.get(&cpid).unwrap().start.unwrap();
-                current_day_work.get_mut(&cpid).unwrap().start = Some(project_start + chrono::Duration::from_std(interval).expect(&format!("Pause duration {:?} is not a valid chrono duration", interval)));
-            },
+/// Sums the work in sheet per project and day, work crossing midnight counts on each day it spans.
+/// Also gives the project worked on at the end of the sheet.
+pub fn parse_sheet(sheet: Sheet, config: &Options) -> Result<(ParsedSheet, JobIdentifier)> {
+    let (intervals, current_project) = work_intervals(&sheet, config)?;
+    Ok((parse_intervals(&intervals)?, JobIdentifier::InternalId(current_project)))
+}
 
-            (illegal_event @ (Event::END(_) | Event::PAUSE(_)), false) => { panic!("Illegal event {} before event BEGIN in timesheet.", illegal_event) }
+/// Sums the work in intervals per project and day, work crossing midnight counts on each day it spans.
+fn parse_intervals(intervals: &[WorkInterval]) -> Result<ParsedSheet> {
+    let mut weeks: BTreeMap<(i32, u32), WeeksWork> = BTreeMap::new();
+    for interval in intervals {
+        for part in interval.split_at_midnights()? {
+            let week = weeks.entry((part.start.iso_week().year(), part.start.iso_week().week())).or_insert_with(|| WeeksWork::of(part.start));
+            let project_day = week.days[part.start.weekday().num_days_from_monday() as usize].entry(part.project).or_default();
+            project_day.total_day += part.duration().to_std().unwrap_or_default();
+            project_day.inferred |= part.inferred;
         }
-    };
-
-    let current_time = Local::now();
-    if current_week_work.week_number == current_time.iso_week().week() && last_dow == current_time.weekday().num_days_from_monday() as usize && current_day_work.get(&cpid).unwrap().total_day == Duration::from_secs(0) {
-        current_day_work.get_mut(&cpid).unwrap().total_day = (current_time - current_day_work.get(&cpid).unwrap().start.unwrap()).to_std().unwrap();
     }
 
-    current_week_work.days[last_dow] = current_day_work;
-    parsed_sheet.push(current_week_work);
-    (parsed_sheet, JobIdentifier::InternalId(cpid))
+    Ok(weeks.into_values().collect())
+}
+
+
+/// Formats duration as "7h, 30m" for the report tables.
+pub(super) fn hours_and_minutes(duration: Duration) -> String {
+    format!("{}h, {}m", duration.as_secs() / 3600, (duration.as_secs() / 60) % 60)
 }
 
 
 #[derive(Default, Clone, Copy)]
 pub struct DaysProjectWork {
     pub total_day: Duration,
-    start: Option<DateTime<Local>>
+    /// The work was ended by the auto end policy, not by an END event.
+    pub inferred: bool
 }
 
 
+/// The Monday starting ISO week week_number of year.
+fn monday(year: i32, week_number: u32) -> NaiveDate {
+    NaiveDate::from_isoywd_opt(year, week_number, Weekday::Mon).expect("Weeks are taken from dates")
+}
+
 #[derive(Default, Clone)]
 pub struct WeeksWork {
     pub days: [DaysWork; 7],
-    pub week_number: u32
+    pub week_number: u32,
+    /// The ISO year the week belongs to, which differs from the calendar year for some days around new year.
+    pub year: i32
 }
 
 impl WeeksWork {
+    /// An empty week, the ISO week of time.
+    fn of(time: DateTime<Local>) -> Self {
+        let week = time.iso_week();
+        WeeksWork { week_number: week.week(), year: week.year(), ..Default::default() }
+    }
+
+    /// The date the week starts on.
+    pub fn monday(&self) -> NaiveDate {
+        monday(self.year, self.week_number)
+    }
+
     pub fn transpose(self) -> TransposedWeeksWork {
-        let mut transposed = TransposedWeeksWork::default();
-        transposed.week_number = self.week_number;
+        let mut transposed = TransposedWeeksWork { week_number: self.week_number, year: self.year, ..Default::default() };
 
-        for day in 0..7 as usize{
+        for day in 0..7 {
             let mut total = Duration::from_secs(0);
             for (cpid, project_day) in self.days[day].iter() {
                 total += project_day.total_day;
+                transposed.total.days[day].inferred |= project_day.inferred;
                 if let Some(project_week) = transposed.projects.get_mut(cpid) {
                     project_week.days[day] = *project_day;
                 } else {
@@ -202,10 +216,58 @@ pub struct TransposedWeeksWork {
     pub total: TransposedWeeksProjectWork,
     pub total_time: Duration,
     pub projects: BTreeMap<usize, TransposedWeeksProjectWork>,
-    pub week_number: u32
+    pub week_number: u32,
+    pub year: i32
+}
+
+impl TransposedWeeksWork {
+    /// The date the week starts on.
+    pub fn monday(&self) -> NaiveDate {
+        monday(self.year, self.week_number)
+    }
+
+    /// The projects of the week grouped by the attribute, with the work of each group summed per day.
+    /// Without an attribute all projects are in one group.
+    pub fn groups(&self, group_by: Option<GroupBy>, config: &Options) -> Result<Vec<ProjectGroup>> {
+        let mut groups: BTreeMap<String, ProjectGroup> = BTreeMap::new();
+
+        for (cpid, project_week) in self.projects.iter() {
+            let project = JobIdentifier::InternalId(*cpid).require_jobtype(config)?;
+            let key = match group_by {
+                Some(GroupBy::Category) => project.category,
+                Some(GroupBy::ProjectId) => project.project_id,
+                None => String::new()
+            };
+
+            let group = groups.entry(key.clone()).or_insert_with(|| ProjectGroup { key, ..Default::default() });
+            group.projects.push(*cpid);
+            for (group_day, project_day) in group.work.days.iter_mut().zip(project_week.days.iter()) {
+                group_day.total_day += project_day.total_day;
+                group_day.inferred |= project_day.inferred;
+            }
+        }
+
+        Ok(groups.into_values().collect())
+    }
 }
 
 #[derive(Default)]
 pub struct TransposedWeeksProjectWork {
     pub days: [DaysProjectWork; 7]
 }
+
+impl TransposedWeeksProjectWork {
+    pub fn total(&self) -> Duration {
+        self.days.iter().map(|day| day.total_day).sum()
+    }
+}
+
+
+#[derive(Default)]
+pub struct ProjectGroup {
+    /// The category or project ID shared by the projects.
+    pub key: String,
+    /// Internal ids of the projects in the group.
+    pub projects: Vec<usize>,
+    pub work: TransposedWeeksProjectWork
+}
@@ -0,0 +1,31 @@
+use timetrack::events::Event;
+use timetrack::options::Schedule;
+use timetrack::views::balance::{balance, days_worked};
+use timetrack::views::viewer::parse_sheet;
+
+mod common;
+use common::{config, at, date, minutes};
+
+
+#[test]
+fn balance_runs_from_the_schedule_start_and_counts_days_without_work() {
+    let config = config();
+    let schedule = Schedule { hours: [7.5, 7.5, 7.5, 7.5, 7.5, 0.0, 0.0], since: Some(date("2026-10-09")) };
+    let (parsed, _) = parse_sheet(vec![
+        Event::BEGIN(at("2026-10-09 08:00")),
+        Event::END(at("2026-10-09 17:00")),
+        Event::BEGIN(at("2026-10-12 08:00")),
+        Event::END(at("2026-10-12 15:00"))
+    ], &config).unwrap();
+
+    let days = balance(&days_worked(&parsed), date("2026-10-14"), &schedule);
+
+    assert_eq!(days.len(), 5);
+    assert_eq!(days[0].date, date("2026-10-09"));
+    assert_eq!(days[0].balance.num_minutes(), 90);
+    assert_eq!(days[1].expected, minutes(0));
+    assert_eq!(days[2].balance.num_minutes(), 90);
+    assert_eq!(days[3].worked, minutes(420));
+    assert_eq!(days[3].balance.num_minutes(), 60);
+    assert_eq!(days[4].difference().num_minutes(), -450);
+}
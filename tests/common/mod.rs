@@ -0,0 +1,32 @@
+#![allow(dead_code)]
+
+use chrono::prelude::*;
+
+use std::time::Duration;
+
+use timetrack::events::{Event, JobIdentifier, parse_time};
+use timetrack::options::{JobType, Options};
+
+
+/// The default config with a second project "b" of internal id 1.
+pub fn config() -> Options {
+    let mut config = Options::default();
+    config.projects.push(JobType { internal_id: 1, u_name: String::from("b"), ..JobType::default() });
+    config
+}
+
+pub fn at(time: &str) -> DateTime<Local> {
+    parse_time(time).unwrap()
+}
+
+pub fn date(date: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+}
+
+pub fn switch(time: &str, project: &str) -> Event {
+    Event::SWITCH(at(time), JobIdentifier::parse(project))
+}
+
+pub fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
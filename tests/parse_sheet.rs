@@ -0,0 +1,139 @@
+use chrono::prelude::*;
+
+use std::time::Duration;
+
+use timetrack::events::{Event, Sheet};
+use timetrack::options::JobType;
+use timetrack::views::viewer::{ParsedSheet, GroupBy, parse_sheet};
+
+mod common;
+use common::{config, at, switch, minutes};
+
+
+/// Work on project during the day of date, as "YYYY-MM-DD".
+fn worked(parsed: &ParsedSheet, project: usize, date: &str) -> Duration {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+    parsed.iter()
+        .find(|week| week.year == date.iso_week().year() && week.week_number == date.iso_week().week())
+        .and_then(|week| week.days[date.weekday().num_days_from_monday() as usize].get(&project))
+        .map(|project_day| project_day.total_day)
+        .unwrap_or_default()
+}
+
+fn parse(sheet: Sheet) -> ParsedSheet {
+    parse_sheet(sheet, &config()).unwrap().0
+}
+
+
+#[test]
+fn sessions_after_a_switch_count_for_the_project_switched_to() {
+    let parsed = parse(vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        switch("2026-10-12 10:00", "b"),
+        Event::END(at("2026-10-12 12:00")),
+        Event::BEGIN(at("2026-10-12 13:00")),
+        Event::END(at("2026-10-12 15:00"))
+    ]);
+
+    assert_eq!(worked(&parsed, 0, "2026-10-12"), minutes(120));
+    assert_eq!(worked(&parsed, 1, "2026-10-12"), minutes(240));
+}
+
+#[test]
+fn switching_back_and_forth_adds_up() {
+    let parsed = parse(vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        switch("2026-10-12 09:00", "b"),
+        switch("2026-10-12 10:00", "example"),
+        switch("2026-10-12 11:30", "b"),
+        Event::END(at("2026-10-12 12:00"))
+    ]);
+
+    assert_eq!(worked(&parsed, 0, "2026-10-12"), minutes(150));
+    assert_eq!(worked(&parsed, 1, "2026-10-12"), minutes(90));
+}
+
+#[test]
+fn pause_in_a_later_session_is_subtracted_once() {
+    let parsed = parse(vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        Event::END(at("2026-10-12 12:00")),
+        Event::BEGIN(at("2026-10-12 13:00")),
+        Event::PAUSE(minutes(30)),
+        Event::END(at("2026-10-12 17:00"))
+    ]);
+
+    assert_eq!(worked(&parsed, 0, "2026-10-12"), minutes(450));
+}
+
+#[test]
+fn timed_pause_spanning_a_switch_is_subtracted_from_both_projects() {
+    let parsed = parse(vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        Event::TIMEDPAUSE(at("2026-10-12 11:45"), at("2026-10-12 12:30")),
+        switch("2026-10-12 12:00", "b"),
+        Event::END(at("2026-10-12 14:00"))
+    ]);
+
+    assert_eq!(worked(&parsed, 0, "2026-10-12"), minutes(225));
+    assert_eq!(worked(&parsed, 1, "2026-10-12"), minutes(90));
+}
+
+#[test]
+fn break_is_not_counted() {
+    let parsed = parse(vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        Event::BREAK(at("2026-10-12 10:00")),
+        switch("2026-10-12 10:30", "b"),
+        Event::RESUME(at("2026-10-12 11:00")),
+        Event::END(at("2026-10-12 12:00"))
+    ]);
+
+    assert_eq!(worked(&parsed, 0, "2026-10-12"), minutes(120));
+    assert_eq!(worked(&parsed, 1, "2026-10-12"), minutes(60));
+}
+
+#[test]
+fn work_crossing_midnight_is_split_between_days_and_weeks() {
+    let parsed = parse(vec![
+        Event::BEGIN(at("2026-10-11 22:00")),
+        Event::END(at("2026-10-12 02:30"))
+    ]);
+
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(worked(&parsed, 0, "2026-10-11"), minutes(120));
+    assert_eq!(worked(&parsed, 0, "2026-10-12"), minutes(150));
+}
+
+#[test]
+fn same_week_number_in_different_years_is_kept_apart() {
+    let parsed = parse(vec![
+        Event::BEGIN(at("2025-10-13 08:00")),
+        Event::END(at("2025-10-13 10:00")),
+        Event::BEGIN(at("2026-10-12 08:00")),
+        Event::END(at("2026-10-12 09:00"))
+    ]);
+
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(worked(&parsed, 0, "2025-10-13"), minutes(120));
+    assert_eq!(worked(&parsed, 0, "2026-10-12"), minutes(60));
+}
+
+#[test]
+fn groups_sum_the_projects_sharing_a_category() {
+    let mut config = config();
+    config.projects.push(JobType { internal_id: 2, u_name: String::from("c"), category: String::from("1"), ..JobType::default() });
+    let (parsed, _) = parse_sheet(vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        switch("2026-10-12 09:00", "b"),
+        switch("2026-10-12 10:30", "c"),
+        Event::END(at("2026-10-12 12:00"))
+    ], &config).unwrap();
+
+    let week = parsed.into_iter().next().unwrap().transpose();
+    let groups = week.groups(Some(GroupBy::Category), &config).unwrap();
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!((groups[0].key.as_str(), groups[0].projects.clone(), groups[0].work.total()), ("0", vec![0, 1], minutes(150)));
+    assert_eq!((groups[1].key.as_str(), groups[1].projects.clone(), groups[1].work.total()), ("1", vec![2], minutes(90)));
+}
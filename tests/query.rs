@@ -0,0 +1,50 @@
+use timetrack::events::Event;
+use timetrack::options::JobType;
+use timetrack::query::TrackedTime;
+use timetrack::views::period::Period;
+
+mod common;
+use common::{config, at, date, switch, minutes};
+
+
+fn tracked() -> TrackedTime {
+    let mut config = config();
+    config.projects.push(JobType { internal_id: 2, u_name: String::from("c"), category: String::from("support"), ..JobType::default() });
+
+    let sheet = vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        switch("2026-10-12 10:00", "b"),
+        Event::END(at("2026-10-12 12:00")),
+        Event::BEGIN(at("2026-10-13 22:00")),
+        switch("2026-10-13 23:00", "c"),
+        Event::END(at("2026-10-14 01:00"))
+    ];
+    TrackedTime::new(&sheet, &config).unwrap()
+}
+
+
+#[test]
+fn total_for_a_project_counts_only_work_inside_the_period() {
+    let tracked = tracked();
+
+    assert_eq!(tracked.total_for("b", &Period::days(date("2026-10-12"), date("2026-10-13")).unwrap()).unwrap(), minutes(180));
+    assert_eq!(tracked.total_for("c", &Period::days(date("2026-10-13"), date("2026-10-13")).unwrap()).unwrap(), minutes(60));
+    assert!(tracked.total_for("unknown", &Period::today().unwrap()).is_err());
+}
+
+#[test]
+fn by_day_splits_work_at_midnight() {
+    let by_day = tracked().by_day(&Period::between(None, None).unwrap()).unwrap();
+
+    assert_eq!(by_day.get(&date("2026-10-12")), Some(&minutes(240)));
+    assert_eq!(by_day.get(&date("2026-10-13")), Some(&minutes(120)));
+    assert_eq!(by_day.get(&date("2026-10-14")), Some(&minutes(60)));
+}
+
+#[test]
+fn by_category_sums_projects_of_the_same_category() {
+    let by_category = tracked().by_category(&Period::between(Some("2026-10-01"), Some("2026-10-31")).unwrap());
+
+    assert_eq!(by_category.get("0"), Some(&minutes(300)));
+    assert_eq!(by_category.get("support"), Some(&minutes(120)));
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::time::Duration;
+
+use timetrack::events::{Event, Sheet};
+use timetrack::store::{SheetStore, JsonFileStore, SqliteStore, SheetFormat, open_store, read_sheet_between, read_sheet_file};
+
+mod common;
+use common::{at, switch, minutes};
+
+
+/// A path in the temporary directory unique to the test, without a file at it.
+fn temporary_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("timetrack-{}-{}", std::process::id(), name));
+    let path = path.to_string_lossy().into_owned();
+    for leftover in [path.clone(), format!("{}.1", path), format!("{}.v1.bak", path), format!("{}.lock", path)] {
+        let _ = fs::remove_file(leftover);
+    }
+    path
+}
+
+/// An empty store of every kind.
+fn stores(name: &str) -> Vec<Box<dyn SheetStore>> {
+    vec![
+        Box::new(JsonFileStore::open(&temporary_path(&format!("{}.time", name)))),
+        Box::new(JsonFileStore::open(&temporary_path(&format!("{}.jsonl", name)))),
+        Box::new(SqliteStore::open(&temporary_path(&format!("{}.sqlite", name))).unwrap())
+    ]
+}
+
+fn events(sheet: &Sheet) -> Vec<String> {
+    sheet.iter().map(|event| format!("{:?}", event)).collect()
+}
+
+fn day() -> Sheet {
+    vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        switch("2026-10-12 10:00", "b"),
+        Event::PAUSE(minutes(30)),
+        Event::END(at("2026-10-12 12:00")),
+        Event::BEGIN(at("2026-10-13 08:00")),
+        Event::END(at("2026-10-13 12:00"))
+    ]
+}
+
+
+#[test]
+fn insert_replace_and_remove_keep_the_sheet_in_order() {
+    for mut store in stores("edits") {
+        for event in day() {
+            store.append(event).unwrap();
+        }
+
+        store.insert(3, switch("2026-10-12 11:00", "example")).unwrap();
+        store.replace(0, Event::BEGIN(at("2026-10-12 07:30"))).unwrap();
+        store.remove(5).unwrap();
+        assert!(store.remove(5).is_ok());
+        assert!(store.remove(5).is_err());
+
+        let mut expected = day();
+        expected.insert(3, switch("2026-10-12 11:00", "example"));
+        expected[0] = Event::BEGIN(at("2026-10-12 07:30"));
+        expected.truncate(5);
+        assert_eq!(events(&store.load().unwrap()), events(&expected), "{:?}", store.format());
+    }
+}
+
+#[test]
+fn load_range_takes_untimed_pauses_at_the_time_before_them() {
+    for mut store in stores("range") {
+        store.write_all(day()).unwrap();
+
+        let range = store.load_range(at("2026-10-12 10:00"), at("2026-10-13 08:00")).unwrap();
+        assert_eq!(events(&range), events(&day()[1..4].to_vec()), "{:?}", store.format());
+
+        // Moving the SWITCH moves the PAUSE after it along
+        store.replace(1, switch("2026-10-12 09:00", "b")).unwrap();
+        let range = store.load_range(at("2026-10-12 10:00"), at("2026-10-13 08:00")).unwrap();
+        assert_eq!(events(&range), events(&vec![Event::END(at("2026-10-12 12:00"))]), "{:?}", store.format());
+    }
+}
+
+#[test]
+fn last_switch_before_finds_the_project_worked_on() {
+    for mut store in stores("switch") {
+        store.write_all(day()).unwrap();
+
+        let last_switch = store.last_switch_before(at("2026-10-13 09:00")).unwrap();
+        assert_eq!(events(&last_switch.into_iter().collect()), events(&vec![switch("2026-10-12 10:00", "b")]), "{:?}", store.format());
+        assert!(store.last_switch_before(at("2026-10-12 10:00")).unwrap().is_none(), "{:?}", store.format());
+    }
+}
+
+#[test]
+fn json_lines_sheets_append_one_line_per_event_and_read_back() {
+    let path = temporary_path("round-trip.jsonl");
+    let mut store = JsonFileStore::open(&path);
+    for event in day() {
+        store.append(event).unwrap();
+    }
+
+    let lines: Vec<String> = fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+    assert_eq!(lines.len(), day().len() + 1);
+    assert!(lines[0].contains("\"version\":2"));
+    assert_eq!(lines[3], "{\"PAUSE\":{\"secs\":1800,\"nanos\":0}}");
+
+    let sheet_file = read_sheet_file(&path).unwrap();
+    assert_eq!(sheet_file.format, SheetFormat::JsonLines);
+    assert_eq!(events(&sheet_file.events), events(&day()));
+}
+
+#[test]
+fn version_1_sheets_are_migrated_and_backed_up() {
+    let path = temporary_path("migrate.time");
+    let original = "[{\"BEGIN\":\"2026-10-12T08:00:00+00:00\"},{\"PAUSE\":{\"secs\":600,\"nanos\":0}},{\"END\":\"2026-10-12T12:00:00+00:00\"}]";
+    fs::write(&path, original).unwrap();
+
+    let sheet_file = read_sheet_file(&path).unwrap();
+    assert_eq!(sheet_file.version, 2);
+    assert_eq!(sheet_file.events.len(), 3);
+    assert!(matches!(sheet_file.events[1], Event::PAUSE(pause) if pause == Duration::from_secs(600)));
+
+    assert_eq!(fs::read_to_string(format!("{}.v1.bak", path)).unwrap(), original);
+    let migrated: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(migrated["version"], 2);
+    assert_eq!(migrated["events"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn ranges_keep_the_work_going_on_at_their_ends_whole() {
+    for path in [temporary_path("between.time"), temporary_path("between.sqlite")] {
+        let mut store = open_store(&path).unwrap();
+        store.write_all(vec![
+            Event::BEGIN(at("2026-10-12 08:00")),
+            Event::END(at("2026-10-12 12:00")),
+            Event::BEGIN(at("2026-10-13 22:00")),
+            switch("2026-10-13 23:00", "b"),
+            Event::BREAK(at("2026-10-13 23:30")),
+            Event::RESUME(at("2026-10-14 00:30")),
+            Event::END(at("2026-10-14 02:00")),
+            Event::BEGIN(at("2026-10-14 08:00")),
+            Event::END(at("2026-10-14 12:00"))
+        ]).unwrap();
+
+        let after_midnight = read_sheet_between(&path, at("2026-10-14 00:00"), at("2026-10-15 00:00")).unwrap();
+        assert_eq!(events(&after_midnight[..1].to_vec()), events(&vec![Event::BEGIN(at("2026-10-13 22:00"))]), "{}", path);
+        assert_eq!(after_midnight.len(), 7, "{}", path);
+
+        let before_midnight = read_sheet_between(&path, at("2026-10-13 00:00"), at("2026-10-14 00:00")).unwrap();
+        assert_eq!(events(&before_midnight), events(&vec![
+            Event::BEGIN(at("2026-10-13 22:00")),
+            switch("2026-10-13 23:00", "b"),
+            Event::BREAK(at("2026-10-13 23:30")),
+            Event::RESUME(at("2026-10-14 00:30")),
+            Event::END(at("2026-10-14 02:00"))
+        ]), "{}", path);
+    }
+}
+
+#[test]
+fn ranges_reach_the_begin_closing_a_day_left_open() {
+    for path in [temporary_path("left-open.time"), temporary_path("left-open.sqlite")] {
+        let mut store = open_store(&path).unwrap();
+        store.write_all(vec![
+            Event::BEGIN(at("2026-10-15 08:00")),
+            Event::BEGIN(at("2026-10-16 08:00")),
+            Event::END(at("2026-10-16 12:00"))
+        ]).unwrap();
+
+        let range = read_sheet_between(&path, at("2026-10-15 00:00"), at("2026-10-16 00:00")).unwrap();
+        assert_eq!(events(&range), events(&vec![
+            Event::BEGIN(at("2026-10-15 08:00")),
+            Event::BEGIN(at("2026-10-16 08:00"))
+        ]), "{}", path);
+    }
+}
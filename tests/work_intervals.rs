@@ -0,0 +1,108 @@
+use chrono::prelude::*;
+
+use timetrack::events::{Event, open_days};
+use timetrack::intervals::work_intervals;
+use timetrack::options::AutoEnd;
+
+mod common;
+use common::{config, at, switch, minutes};
+
+
+fn yesterday_at(hour: u32) -> DateTime<Local> {
+    let yesterday = Local::now().naive_local().date().pred_opt().unwrap();
+    Local.from_local_datetime(&yesterday.and_hms_opt(hour, 0, 0).unwrap()).earliest().unwrap()
+}
+
+
+#[test]
+fn intervals_point_back_to_their_events() {
+    let sheet = vec![
+        Event::BEGIN(at("2026-10-12 08:00")),
+        Event::PAUSE(minutes(15)),
+        switch("2026-10-12 10:00", "b"),
+        Event::TIMEDPAUSE(at("2026-10-12 12:00"), at("2026-10-12 12:30")),
+        Event::END(at("2026-10-12 14:00"))
+    ];
+    let (intervals, current_project) = work_intervals(&sheet, &config()).unwrap();
+
+    let spans: Vec<_> = intervals.iter().map(|interval| (interval.project, interval.start, interval.end, interval.source_events.clone())).collect();
+    assert_eq!(spans, vec![
+        (0, at("2026-10-12 08:15"), at("2026-10-12 10:00"), vec![0, 1, 2]),
+        (1, at("2026-10-12 10:00"), at("2026-10-12 12:00"), vec![2, 3, 4]),
+        (1, at("2026-10-12 12:30"), at("2026-10-12 14:00"), vec![2, 3, 4])
+    ]);
+    assert_eq!(current_project, 1);
+}
+
+#[test]
+fn intervals_split_at_midnight() {
+    let sheet = vec![
+        Event::BEGIN(at("2026-10-12 21:00")),
+        Event::END(at("2026-10-13 01:00"))
+    ];
+    let (intervals, _) = work_intervals(&sheet, &config()).unwrap();
+    let parts = intervals[0].split_at_midnights().unwrap();
+
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].end, at("2026-10-13 00:00"));
+    assert_eq!(parts[1].start, at("2026-10-13 00:00"));
+    assert_eq!(parts.iter().map(|part| part.duration().num_minutes()).sum::<i64>(), 240);
+}
+
+#[test]
+fn work_begun_yesterday_counts_until_now_while_the_policy_has_not_ended_it() {
+    let begun = yesterday_at(23);
+    let sheet = vec![Event::BEGIN(begun)];
+    let mut config = config();
+    config.auto_end = AutoEnd::AfterHours(48.0);
+
+    assert!(open_days(&sheet, config.auto_end).is_empty());
+    let (intervals, _) = work_intervals(&sheet, &config).unwrap();
+    assert_eq!(intervals.len(), 1);
+    assert_eq!(intervals[0].start, begun);
+    assert!(!intervals[0].inferred);
+    assert!(intervals[0].duration() > chrono::Duration::hours(1));
+}
+
+#[test]
+fn work_begun_yesterday_is_ended_once_the_policy_would_have_ended_it() {
+    let sheet = vec![Event::BEGIN(yesterday_at(8))];
+    let mut config = config();
+    config.auto_end = AutoEnd::AtTime(NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+
+    assert_eq!(open_days(&sheet, config.auto_end).len(), 1);
+    let (intervals, _) = work_intervals(&sheet, &config).unwrap();
+    assert_eq!(intervals.len(), 1);
+    assert_eq!(intervals[0].end, yesterday_at(17));
+    assert!(intervals[0].inferred);
+
+    config.auto_end = AutoEnd::Never;
+    assert!(work_intervals(&sheet, &config).unwrap().0.is_empty());
+}
+
+#[test]
+fn days_never_ended_are_ended_after_their_work_past_midnight() {
+    let sheet = vec![
+        Event::BEGIN(at("2026-10-14 22:00")),
+        Event::BREAK(at("2026-10-14 23:30")),
+        Event::RESUME(at("2026-10-15 00:30")),
+        switch("2026-10-15 01:00", "b"),
+        Event::BEGIN(at("2026-10-15 22:00")),
+        Event::END(at("2026-10-15 23:00"))
+    ];
+    let mut config = config();
+    config.auto_end = AutoEnd::AtTime(NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+
+    let days = open_days(&sheet, config.auto_end);
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].index, 4);
+    assert_eq!(config.auto_end.end_time(&days[0]), Some(at("2026-10-15 01:00")));
+
+    let (intervals, _) = work_intervals(&sheet, &config).unwrap();
+    let spans: Vec<_> = intervals.iter().map(|interval| (interval.project, interval.start, interval.end, interval.inferred)).collect();
+    assert_eq!(spans, vec![
+        (0, at("2026-10-14 22:00"), at("2026-10-14 23:30"), false),
+        (0, at("2026-10-15 00:30"), at("2026-10-15 01:00"), false),
+        (1, at("2026-10-15 22:00"), at("2026-10-15 23:00"), false)
+    ]);
+}